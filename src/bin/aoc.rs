@@ -0,0 +1,43 @@
+//! Downloads inputs and submits answers, see [`advent_of_code_2023::client`] for the
+//! configuration.
+
+use advent_of_code_2023::client::{Client, Config};
+use anyhow::{bail, Context};
+
+const USAGE: &str = "usage:
+    aoc input fetch <day>
+    aoc submit <day> <part> <answer>";
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let parse_day = |day: &str| -> anyhow::Result<u8> {
+        let day = day.trim_start_matches('p').parse().context("parsing day")?;
+        if !(1..=25).contains(&day) {
+            bail!("day must be in 1..=25");
+        }
+        Ok(day)
+    };
+
+    match args[..] {
+        ["input", "fetch", day] => {
+            let client = Client::new(Config::from_env()?);
+            let path = client.fetch_input(parse_day(day)?)?;
+            println!("Input is in {}", path.display());
+        }
+        ["submit", day, part, answer] => {
+            let part = match part {
+                "1" => 1,
+                "2" => 2,
+                _ => bail!("part must be 1 or 2"),
+            };
+            let client = Client::new(Config::from_env()?);
+            let verdict = client.submit(parse_day(day)?, part, answer)?;
+            println!("{answer} is {verdict}");
+        }
+        _ => bail!(USAGE),
+    }
+
+    Ok(())
+}
@@ -1,43 +1,450 @@
-use std::fs;
+use std::{collections::VecDeque, fs};
 
-fn main() {
-    let content = fs::read_to_string("inputs/p1/calibration.txt").unwrap();
+use anyhow::{bail, Context};
+use serde::Serialize;
+
+/// What counts as a digit in the calibration document
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    /// Only `0`..=`9` (part 1)
+    Digits,
+    /// Also the English words `one`..=`nine` (part 2)
+    Words,
+}
 
-    let mut code = 0;
-    for l in content.lines() {
-        // find first, then find last. Add to code
-        for i in 0..l.len() {
-            if let Some(digit) = str_to_digit(&l[i..]) {
-                code += 10 * digit;
-                break;
+impl Mode {
+    fn vocabulary(self) -> Vocabulary {
+        let mut vocabulary = Vocabulary((0..10).map(|d| (d.to_string(), d)).collect());
+        if self == Mode::Words {
+            let words = [
+                "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+            ];
+            vocabulary
+                .0
+                .extend(words.iter().zip(1..).map(|(w, d)| (w.to_string(), d)));
+        }
+        vocabulary
+    }
+}
+
+/// Tokens that stand for a digit, with their value
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Vocabulary(Vec<(String, u32)>);
+
+impl Vocabulary {
+    /// Parses one `<token> <value>` pair per line. Empty lines and lines starting with `#` are
+    /// skipped.
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut tokens = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
+            let Some((token, value)) = line.split_once(char::is_whitespace) else {
+                bail!("line {}: expected `<token> <value>`, got {line:?}", i + 1);
+            };
+            tokens
+                .push(Self::entry(token, value.trim()).with_context(|| format!("line {}", i + 1))?);
+        }
+        Ok(Self(tokens))
+    }
+
+    fn entry(token: &str, value: &str) -> anyhow::Result<(String, u32)> {
+        if token.is_empty() {
+            bail!("empty token for {value:?}");
         }
-        for i in (0..l.len()).rev() {
-            if let Some(digit) = str_to_digit(&l[i..]) {
-                code += digit;
-                break;
+        let value = value
+            .parse()
+            .ok()
+            .filter(|&v| v < 10)
+            .with_context(|| format!("{value:?} is not a digit"))?;
+        Ok((token.to_string(), value))
+    }
+
+    /// Digits, plus the tokens given with `--vocab=<file>` and `--word=<token>=<value>`.
+    /// `--vocab=words` adds the English words of part 2, and `--vocab=digits` nothing.
+    /// Returns `None` without any of those.
+    fn from_args() -> anyhow::Result<Option<Self>> {
+        let mut vocabulary: Option<Self> = None;
+        for arg in std::env::args().skip(1) {
+            let tokens = if let Some(path) = arg.strip_prefix("--vocab=") {
+                match path {
+                    "digits" => vec![],
+                    "words" => Mode::Words.vocabulary().0,
+                    _ => {
+                        let text =
+                            fs::read_to_string(path).with_context(|| format!("reading {path}"))?;
+                        Self::parse(&text)
+                            .with_context(|| format!("parsing {path}"))?
+                            .0
+                    }
+                }
+            } else if let Some(word) = arg.strip_prefix("--word=") {
+                let Some((token, value)) = word.split_once('=') else {
+                    bail!("expected `--word=<token>=<value>`, got {arg:?}");
+                };
+                vec![Self::entry(token, value)?]
+            } else {
+                continue;
+            };
+            vocabulary
+                .get_or_insert_with(|| Mode::Digits.vocabulary())
+                .0
+                .extend(tokens);
+        }
+        Ok(vocabulary)
+    }
+}
+
+/// Aho–Corasick automaton over bytes, with the transitions of every state precomputed so that
+/// scanning is a single table lookup per byte.
+struct Automaton {
+    next: Vec<[usize; 256]>,
+    /// Patterns ending at each state, with their length
+    outputs: Vec<Vec<(usize, usize)>>,
+}
+
+impl Automaton {
+    fn new<'p>(patterns: impl IntoIterator<Item = &'p [u8]>) -> Self {
+        // trie, 0 being the root and the failure state
+        let mut next = vec![[0; 256]];
+        let mut outputs = vec![vec![]];
+        for (i, pattern) in patterns.into_iter().enumerate() {
+            let mut state = 0;
+            for &b in pattern {
+                if next[state][b as usize] == 0 {
+                    next.push([0; 256]);
+                    outputs.push(vec![]);
+                    next[state][b as usize] = next.len() - 1;
+                }
+                state = next[state][b as usize];
             }
+            outputs[state].push((i, pattern.len()));
         }
+
+        // breadth first, so that the failure state of a node is done before the node itself.
+        // Missing transitions are taken from the failure state.
+        let mut fail = vec![0; next.len()];
+        let mut queue: VecDeque<_> = next[0].iter().copied().filter(|&s| s != 0).collect();
+        while let Some(state) = queue.pop_front() {
+            let out = outputs[fail[state]].clone();
+            outputs[state].extend(out);
+            let fallback = next[fail[state]];
+            for (child, &f) in next[state].iter_mut().zip(&fallback) {
+                if *child == 0 {
+                    *child = f;
+                } else {
+                    fail[*child] = f;
+                    queue.push_back(*child);
+                }
+            }
+        }
+
+        Self { next, outputs }
     }
 
-    println!("Code (with spelled digits): {code}");
+    /// Calls `found(end, pattern, length)` for every match, by increasing end, until it returns
+    /// `false`.
+    fn scan(
+        &self,
+        text: impl Iterator<Item = u8>,
+        mut found: impl FnMut(usize, usize, usize) -> bool,
+    ) {
+        let mut state = 0;
+        for (i, b) in text.enumerate() {
+            state = self.next[state][b as usize];
+            for &(pattern, len) in &self.outputs[state] {
+                if !found(i + 1, pattern, len) {
+                    return;
+                }
+            }
+        }
+    }
 }
 
-fn str_to_digit(s: &str) -> Option<u32> {
-    let digit = s.chars().next().and_then(|c| c.to_digit(10)).or_else(|| {
-        [
-            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-        ]
-        .iter()
-        .enumerate()
-        .find_map(|(i, d)| {
-            if s.starts_with(d) {
-                Some(i as u32 + 1)
-            } else {
-                None
+/// A token found in a line, `start..end` being its byte range
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+struct Match<'l> {
+    token: &'l str,
+    start: usize,
+    end: usize,
+    value: u32,
+}
+
+/// Finds the first and last token of a [`Vocabulary`] in a line. The line is scanned forward for
+/// the first one and backward (with the tokens reversed) for the last one, so overlapping tokens
+/// (`oneight`) are found independently.
+struct Matcher {
+    vocabulary: Vocabulary,
+    forward: Automaton,
+    backward: Automaton,
+    max_len: usize,
+}
+
+impl Matcher {
+    fn new(vocabulary: Vocabulary) -> Self {
+        let tokens = || vocabulary.0.iter().map(|(t, _)| t.as_bytes());
+        let reversed: Vec<Vec<u8>> = tokens()
+            .map(|t| t.iter().rev().copied().collect())
+            .collect();
+        Self {
+            forward: Automaton::new(tokens()),
+            backward: Automaton::new(reversed.iter().map(Vec::as_slice)),
+            max_len: tokens().map(<[u8]>::len).max().unwrap_or(0),
+            vocabulary,
+        }
+    }
+
+    fn token<'l>(&self, line: &'l str, pattern: usize, start: usize, end: usize) -> Match<'l> {
+        Match {
+            token: &line[start..end],
+            start,
+            end,
+            value: self.vocabulary.0[pattern].1,
+        }
+    }
+
+    /// Token starting the earliest, the longest one if several start there
+    fn first<'l>(&self, line: &'l str) -> Option<Match<'l>> {
+        let (start, end, pattern) = self.leftmost(&self.forward, line.bytes())?;
+        Some(self.token(line, pattern, start, end))
+    }
+
+    /// Token ending the latest, the longest one if several end there
+    fn last<'l>(&self, line: &'l str) -> Option<Match<'l>> {
+        let (start, end, pattern) = self.leftmost(&self.backward, line.bytes().rev())?;
+        Some(self.token(line, pattern, line.len() - end, line.len() - start))
+    }
+
+    /// Leftmost-longest match of the automaton, as `(start, end, pattern)`
+    fn leftmost(
+        &self,
+        automaton: &Automaton,
+        text: impl Iterator<Item = u8>,
+    ) -> Option<(usize, usize, usize)> {
+        let mut best: Option<(usize, usize, usize)> = None;
+        automaton.scan(text, |end, pattern, len| {
+            let start = end - len;
+            if best.is_none_or(|(s, e, _)| (start, e) < (s, end)) {
+                best = Some((start, end, pattern));
             }
-        })
-    });
+            // later matches start after `end - max_len`
+            best.is_none_or(|(s, _, _)| end <= s + self.max_len)
+        });
+        best
+    }
 
-    digit
+    /// First and last digit of the line, as a two-digit number
+    fn calibration_value(&self, line: &str) -> Option<u32> {
+        Some(10 * self.first(line)?.value + self.last(line)?.value)
+    }
+
+    fn report<'l>(&self, content: &'l str) -> Vec<LineReport<'l>> {
+        content
+            .lines()
+            .enumerate()
+            .map(|(i, text)| {
+                let (first, last) = (self.first(text), self.last(text));
+                LineReport {
+                    line: i + 1,
+                    text,
+                    first,
+                    last,
+                    value: first.zip(last).map(|(f, l)| 10 * f.value + l.value),
+                }
+            })
+            .collect()
+    }
+}
+
+/// How a line was calibrated, for `--report`. `value` is `None` if the line has no digit.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct LineReport<'l> {
+    line: usize,
+    text: &'l str,
+    first: Option<Match<'l>>,
+    last: Option<Match<'l>>,
+    value: Option<u32>,
+}
+
+/// Prints the report as a table, or as JSON. Lines without digit are warned about on stderr.
+fn print_report(report: &[LineReport], json: bool) -> anyhow::Result<()> {
+    for l in report.iter().filter(|l| l.value.is_none()) {
+        eprintln!(
+            "warning: line {} has no digit, it counts as 0: {:?}",
+            l.line, l.text
+        );
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+
+    let width = report.iter().map(|l| l.text.len()).max().unwrap_or(0);
+    let token = |m: Option<Match>| match m {
+        Some(m) => format!("{}@{}..{}", m.token, m.start, m.end),
+        None => "-".to_string(),
+    };
+    for l in report {
+        println!(
+            "{:>5} {:<width$}  {:<16} {:<16} {}",
+            l.line,
+            l.text,
+            token(l.first),
+            token(l.last),
+            l.value.map_or("-".to_string(), |v| v.to_string())
+        );
+    }
+    let total: u32 = report.iter().filter_map(|l| l.value).sum();
+    println!("Total: {total}");
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let content = fs::read_to_string("inputs/p1/calibration.txt").unwrap();
+    let custom = Vocabulary::from_args()?;
+
+    // Per-line report instead of the totals, with the custom vocabulary if any
+    let report = std::env::args().skip(1).find(|a| a.starts_with("--report"));
+    if let Some(report) = report {
+        let json = match report.as_str() {
+            "--report" => false,
+            "--report=json" => true,
+            a => bail!("expected `--report[=json]`, got {a:?}"),
+        };
+        let vocabulary = custom.unwrap_or_else(|| Mode::Words.vocabulary());
+        return print_report(&Matcher::new(vocabulary).report(&content), json);
+    }
+
+    let code = calibration(&content, &Matcher::new(Mode::Digits.vocabulary()));
+    println!("[PART 1] Code: {code}");
+
+    let code = calibration(&content, &Matcher::new(Mode::Words.vocabulary()));
+    println!("[PART 2] Code (with spelled digits): {code}");
+
+    if let Some(vocabulary) = custom {
+        let code = calibration(&content, &Matcher::new(vocabulary));
+        println!("Code (with custom vocabulary): {code}");
+    }
+
+    Ok(())
+}
+
+/// Sum of the calibration values of all lines. Lines without digits count as 0.
+fn calibration(content: &str, matcher: &Matcher) -> u32 {
+    content
+        .lines()
+        .filter_map(|l| matcher.calibration_value(l))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{calibration, LineReport, Match, Matcher, Mode, Vocabulary};
+
+    fn value(line: &str, mode: Mode) -> Option<u32> {
+        Matcher::new(mode.vocabulary()).calibration_value(line)
+    }
+
+    #[test]
+    fn examples() {
+        let digits = Matcher::new(Mode::Digits.vocabulary());
+        let words = Matcher::new(Mode::Words.vocabulary());
+
+        let part1 = "1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\n";
+        assert_eq!(calibration(part1, &digits), 142);
+        assert_eq!(calibration(part1, &words), 142);
+
+        let part2 = "two1nine\neightwothree\nabcone2threexyz\nxtwone3four\n\
+                     4nineeightseven2\nzoneight234\n7pqrstsixteen\n";
+        assert_eq!(calibration(part2, &words), 281);
+        // `eightwothree` has no digit, and counts as 0
+        assert_eq!(calibration(part2, &digits), 11 + 22 + 33 + 42 + 24 + 77);
+    }
+
+    #[test]
+    fn overlapping_words() {
+        assert_eq!(value("eightwo", Mode::Words), Some(82));
+        assert_eq!(value("oneight", Mode::Words), Some(18));
+        assert_eq!(value("xoneightx", Mode::Words), Some(18));
+        assert_eq!(value("twone", Mode::Words), Some(21));
+        assert_eq!(value("oneight", Mode::Digits), None);
+        assert_eq!(value("1oneight", Mode::Digits), Some(11));
+    }
+
+    #[test]
+    fn custom_vocabulary() {
+        let german = Vocabulary::parse("# German\neins 1\nzwei 2\ndrei 3\nacht 8\n").unwrap();
+        let german = Matcher::new(german);
+        assert_eq!(german.calibration_value("xzweinsx"), Some(21));
+        assert_eq!(german.calibration_value("achtzwei4drei"), Some(83));
+
+        let roman = [
+            "I 1", "II 2", "III 3", "IV 4", "V 5", "VI 6", "VII 7", "VIII 8", "IX 9",
+        ];
+        let roman = Matcher::new(Vocabulary::parse(&roman.join("\n")).unwrap());
+        // longest token at the same start
+        assert_eq!(
+            roman.first("xVIIIx"),
+            Some(Match {
+                token: "VIII",
+                start: 1,
+                end: 5,
+                value: 8
+            })
+        );
+        // longest token at the same end
+        assert_eq!(
+            roman.last("xVIIIx"),
+            Some(Match {
+                token: "VIII",
+                start: 1,
+                end: 5,
+                value: 8
+            })
+        );
+        assert_eq!(roman.calibration_value("IXabIV"), Some(94));
+
+        assert!(Vocabulary::parse("zero").is_err());
+        assert!(Vocabulary::parse("zero 10").is_err());
+        assert!(Vocabulary::entry("", "5").is_err());
+        assert_eq!(
+            Vocabulary::parse("zero 0").unwrap(),
+            Vocabulary(vec![("zero".to_string(), 0)])
+        );
+    }
+
+    #[test]
+    fn report() {
+        let matcher = Matcher::new(Mode::Words.vocabulary());
+        let report = matcher.report("xtwone3\nnothing\n");
+        assert_eq!(
+            report[0],
+            LineReport {
+                line: 1,
+                text: "xtwone3",
+                first: Some(Match {
+                    token: "two",
+                    start: 1,
+                    end: 4,
+                    value: 2
+                }),
+                last: Some(Match {
+                    token: "3",
+                    start: 6,
+                    end: 7,
+                    value: 3
+                }),
+                value: Some(23),
+            }
+        );
+        assert_eq!(report[1].first, None);
+        assert_eq!(report[1].value, None);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json[0]["first"]["token"], "two");
+        assert_eq!(json[1]["value"], serde_json::Value::Null);
+    }
 }
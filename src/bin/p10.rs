@@ -1,34 +1,47 @@
-use colored::Colorize;
-use std::fs;
+use std::{error::Error, fs};
 
-struct Walker<'s> {
-    map: &'s [u8],
+use advent_of_code_2023::{
+    playback::{Playback, Step},
+    render::{Color, Frame, Style, Target},
+};
+
+/// The two walkers of part 1, leaving S in opposite directions along the loop until they meet
+struct Walkers<'c> {
+    content: &'c [u8],
+    /// Includes the newline
     width: usize,
-    height: usize,
-    /// X,Y
-    pos: (usize, usize),
-    /// previous position, to avoid going backwards.
-    prev: (usize, usize),
-    walk_len: usize,
+    /// Loop tiles in walking order, starting from S
+    path: Vec<(usize, usize)>,
+    steps: usize,
 }
 
-impl<'s> Walker<'s> {
-    pub fn new(x: usize, y: usize, map: &'s [u8], width: usize, height: usize) -> Self {
-        assert_eq!(map.len(), width * height);
-        Self {
-            map,
-            width,
-            height,
-            pos: (x, y),
-            prev: (x, y),
-            walk_len: 0,
+impl Step for Walkers<'_> {
+    fn step(&mut self) -> bool {
+        if 2 * self.steps >= self.path.len() {
+            return false;
         }
+        self.steps += 1;
+        true
     }
 
-    pub fn walk(&mut self) {}
+    fn frame(&self) -> Frame {
+        let (a, b) = (self.steps, self.path.len() - self.steps);
+        let mut frame = Frame::new(self.width - 1, self.content.len() / self.width, |x, y| {
+            self.content[x + y * self.width] as char
+        });
+        // nothing walked before the first step
+        if self.steps > 0 {
+            frame.highlight(
+                self.path[..=a].iter().chain(&self.path[b..]).copied(),
+                Color::Green,
+            );
+            frame.highlight([self.path[a], self.path[b]], Color::Magenta);
+        }
+        frame
+    }
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
     // it's ascii, rust strings don't allow indexing normally, since they are UTF-8
     let content = fs::read("inputs/p10/pipes.txt").expect("reading input file pipes.txt");
     let width = 1 + content
@@ -89,11 +102,13 @@ fn main() {
     let mut walker = (start_segments.next().unwrap(), s_pos, 1);
     let mut loop_bitmap = vec![0i32; content.len()];
     loop_bitmap[pos_to_idx(s_pos.0, s_pos.1)] = 1;
+    let mut loop_path = vec![s_pos];
     println!("Start with S = {s_pos:?}, walker = {walker:?}");
 
     while pipe(walker.0 .0, walker.0 .1).copied() != Some(b'S') {
         // set bitmap, to be used for part 2
         loop_bitmap[pos_to_idx(walker.0 .0, walker.0 .1)] = walker.2 + 1;
+        loop_path.push(walker.0);
         let next = next_segments(walker.0, walker.1).next().unwrap();
 
         walker.1 = walker.0;
@@ -103,6 +118,16 @@ fn main() {
 
     println!("[PART 1] Distance to furthest point: {}", walker.2 / 2,);
 
+    if let Some(playback) = Playback::from_args("p10")? {
+        let mut walkers = Walkers {
+            content: &content,
+            width,
+            path: loop_path,
+            steps: 0,
+        };
+        playback.run(&mut walkers, "walkers")?;
+    }
+
     // now, for part 2, we need to replace S with the correct pipe piece equivalent
     let content = {
         let adj = [
@@ -139,31 +164,12 @@ fn main() {
     };
     let pipe = |x: usize, y: usize| content.get(pos_to_idx(x, y));
 
-    // Print the Bitmap
-    println!("Bitmap:");
-    let s = &mut [0; 1][..];
-    loop_bitmap
-        .chunks(width)
-        .zip(content.chunks(width))
-        .for_each(|(bit_row, c_row)| {
-            bit_row.iter().zip(c_row.iter()).for_each(|(&b, &e)| {
-                print!("{}", {
-                    s[0] = e;
-                    let st = std::str::from_utf8(s).unwrap();
-                    if b != 0 {
-                        st.green()
-                    } else {
-                        st.red()
-                    }
-                })
-            });
-        });
-
     // An adaptation of the winding number algorithm could be good. Having a bitmap marked with the
     // pipe path allows to then scan every line. Each time we encounter a pipe, we flip a boolean
     // (`in_loop`, initially `false`). Then every tile that is not part of the loop that we
     // encounter while it's `true` is inside of the loop. We count them.
 
+    let mut inside = vec![];
     let tiles: u32 = loop_bitmap
         .chunks(width)
         .enumerate()
@@ -179,9 +185,6 @@ fn main() {
                     // Definition of "upwards": the tile above (or below, depending on 'J' or '7',
                     // ...) has greater distance than the current one
 
-                    let s = &mut [0];
-                    let p = (*pipe(col_id, row_id).unwrap() as char).encode_utf8(s);
-
                     let winding_incr = match (tile_is_loop, pipe(col_id, row_id).unwrap()) {
                         (0, _) => 0,
                         (d, b'L' | b'J') => loop_bitmap
@@ -204,31 +207,66 @@ fn main() {
                         }
                         _ => 0,
                     };
-                    let next_winding = winding + winding_incr;
-                    print!(
-                        "{}",
-                        match next_winding.cmp(&winding) {
-                            std::cmp::Ordering::Less => p.red(),
-                            std::cmp::Ordering::Equal =>
-                                if winding != 0 {
-                                    if tile_is_loop != 0 {
-                                        p.blue()
-                                    } else {
-                                        p.yellow().on_purple()
-                                    }
-                                } else {
-                                    p.clear()
-                                },
-                            std::cmp::Ordering::Greater => p.green(),
-                        }
-                    );
-                    (
-                        count + (winding != 0 && tile_is_loop == 0) as u32,
-                        next_winding,
-                    )
+                    let is_inside = winding != 0 && tile_is_loop == 0;
+                    if is_inside {
+                        inside.push((col_id, row_id));
+                    }
+                    (count + is_inside as u32, winding + winding_incr)
                 })
                 .0
         })
         .sum();
     println!("[PART 2] Tiles in loop: {tiles}");
+
+    if let Some(target) = Target::from_args("p10")? {
+        // last column of each row is the newline
+        let mut frame = Frame::new(width - 1, height, |x, y| *pipe(x, y).unwrap() as char);
+        frame.style_with(|x, y, _| match loop_bitmap[pos_to_idx(x, y)] {
+            0 => Style::fg(Color::Red),
+            _ => Style::fg(Color::Green),
+        });
+        frame.highlight(inside, Color::Magenta);
+        frame.render(&target, "loop")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use advent_of_code_2023::{playback::Step, render::Frame};
+
+    use crate::Walkers;
+
+    #[test]
+    fn walkers_frames() {
+        let content = b"S-7\n|.|\nL-J\n";
+        let path = vec![
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 1),
+        ];
+        let mut walkers = Walkers {
+            content,
+            width: 4,
+            path,
+            steps: 0,
+        };
+        let plain = Frame::new(3, 3, |x, y| content[x + y * 4] as char);
+
+        // frame 0 highlights nothing
+        assert_eq!(walkers.frame(), plain);
+
+        assert!(walkers.step());
+        assert_ne!(walkers.frame(), plain);
+        while walkers.step() {}
+        // the walkers meet on the opposite side of the loop
+        assert_eq!(walkers.steps, 4);
+        walkers.frame();
+    }
 }
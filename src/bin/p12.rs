@@ -7,6 +7,7 @@
 //! 2. Check if the current set exists starting after the spaces (`?` or `#`)
 //! 3. If both conditions are true, run the operation again on the remainder of the string/sets and
 //!    add the result to count
+//!
 //! Once the loop is over, return the count.
 //!
 //! This operation is memoized for part 2, otherwise runtime quickly approaches balls.
@@ -76,9 +77,10 @@ fn is_broken(c: char) -> bool {
 /// Advances set-by-set recursively.
 /// 1. Checks if there is a valid space prefix of length >= 1.
 /// 2. Checks if there is a valid set of length sets[0].
+///
 /// If both hold, it advances.
 /// - If sets is empty, checks if the string only contains spaces. If so, return 1 (valid config),
-/// otherwise 0 (invalid config, broken springs left at end of the row).
+///   otherwise 0 (invalid config, broken springs left at end of the row).
 ///
 /// # Memoization
 ///
@@ -124,8 +126,6 @@ fn recurse(
 
 #[cfg(test)]
 mod tests {
-    use itertools::Itertools;
-
     use crate::count_arrangements;
 
     #[test]
@@ -1,4 +1,9 @@
-use std::fs;
+use std::{error::Error, fs};
+
+use advent_of_code_2023::{
+    playback::{Playback, Step},
+    render::{Color, Frame, Style, Target},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Cell {
@@ -11,7 +16,30 @@ const _: () = assert!(size_of::<Cell>() == 1);
 
 type Field = [Cell];
 
-fn main() {
+/// Tilts the field one direction at a time, in spin cycle order (north, west, south, east)
+struct SpinCycle {
+    field: Vec<Cell>,
+    width: usize,
+    height: usize,
+    tilts: usize,
+}
+
+impl Step for SpinCycle {
+    fn step(&mut self) -> bool {
+        let tilts: [fn(&mut Field, usize, usize); 4] =
+            [move_north, move_west, move_south, move_east];
+        tilts[self.tilts % 4](&mut self.field, self.width, self.height);
+        self.tilts += 1;
+        true
+    }
+
+    fn frame(&self) -> Frame {
+        frame(&self.field, self.width, self.height)
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let render = Target::from_args("p14")?;
     let content = fs::read_to_string("inputs/p14/stones.txt").expect("reading input file");
     let width = content.lines().next().unwrap().len();
     let mut field: Vec<_> = content
@@ -27,10 +55,23 @@ fn main() {
         .collect();
     let height = field.len() / width;
 
+    if let Some(playback) = Playback::from_args("p14")? {
+        let mut spin = SpinCycle {
+            field: field.clone(),
+            width,
+            height,
+            tilts: 0,
+        };
+        playback.run(&mut spin, "spin-cycle")?;
+    }
+
     move_north(&mut field, width, height);
     let part1 = compute_load(&field, width, height);
 
     println!("[PART 1] Moving north causes load {part1}");
+    if let Some(target) = &render {
+        frame(&field, width, height).render(target, "north")?;
+    }
 
     // For part2, looks like I actually have to move the stones...
     // I will implement an Iterator-based way to move stones in a certain line, towards the start.
@@ -84,15 +125,33 @@ fn main() {
     let cycle_pos = (cycles - start - 1) % len;
     let part2 = configurations[start + cycle_pos].1;
     println!("[PART 2] Load should be the same as cycle element {cycle_pos}, aka load {part2}");
+    if let Some(target) = &render {
+        frame(&configurations[start + cycle_pos].0, width, height).render(target, "cycles")?;
+    }
+
+    Ok(())
+}
+
+fn frame(field: &Field, width: usize, height: usize) -> Frame {
+    let mut frame = Frame::new(width, height, |x, y| match field[y * width + x] {
+        Cell::Fixed => '#',
+        Cell::Rolling => 'O',
+        Cell::Empty => '.',
+    });
+    frame.style_with(|_, _, c| match c {
+        'O' => Style::fg(Color::Yellow),
+        '#' => Style::fg(Color::Blue),
+        _ => Style::default(),
+    });
+    frame
 }
 
 fn compute_load(field: &Field, width: usize, height: usize) -> usize {
     let mut load = 0;
     for col in 0..width {
         for row in 0..height {
-            match field[row * width + col] {
-                Cell::Rolling => load += height - row,
-                _ => (),
+            if field[row * width + col] == Cell::Rolling {
+                load += height - row;
             }
         }
     }
@@ -196,7 +255,7 @@ mod tests {
         )];
 
         for (input, expected) in values {
-            assert_eq!(move_line(input.into_iter().copied()), expected);
+            assert_eq!(move_line(input.iter().copied()), expected);
         }
     }
 
@@ -1,6 +1,9 @@
 use std::fs;
 
-fn main() {
+use advent_of_code_2023::explore::{self, Command, Explore};
+use anyhow::Context;
+
+fn main() -> std::io::Result<()> {
     let content = fs::read_to_string("inputs/p15/input.txt")
         .expect("reading input file containing initialization sequence");
     let part1 = content
@@ -15,7 +18,7 @@ fn main() {
         .split(',')
         .filter_map(parse_op)
         .fold(boxes, |mut acc, op| {
-            let _ = match op {
+            match op {
                 Op::Remove(label) => acc[hash_str(label) as usize].retain(|&(l, _)| l != label),
                 Op::Add(label, fc) => {
                     if let Some(replaced) = acc[hash_str(label) as usize]
@@ -33,18 +36,62 @@ fn main() {
 
     // sum of focusing power
     let part2: usize = final_boxes
-        .into_iter()
+        .iter()
         .enumerate()
         .map(|(idx, bx)| {
             (idx + 1)
-                * bx.into_iter()
+                * bx.iter()
                     .enumerate()
-                    .map(|(slot, (_, fc))| (slot + 1) * fc as usize)
+                    .map(|(slot, &(_, fc))| (slot + 1) * fc as usize)
                     .sum::<usize>()
         })
         .sum();
 
     println!("[PART 2] Sum of focusing power: {part2}");
+
+    if explore::requested() {
+        explore::repl("p15", &mut Explorer { final_boxes })?;
+    }
+
+    Ok(())
+}
+
+struct Explorer<'s> {
+    final_boxes: Vec<Vec<(&'s str, u8)>>,
+}
+
+impl Explore for Explorer<'_> {
+    const COMMANDS: &'static [Command] = &[
+        Command {
+            name: "hash",
+            usage: "hash <label>",
+            help: "HASH of a label, i.e. its box",
+        },
+        Command {
+            name: "box",
+            usage: "box <n>",
+            help: "lenses in a box after the initialization sequence",
+        },
+    ];
+
+    fn run(&mut self, name: &str, args: &str) -> anyhow::Result<String> {
+        match name {
+            "hash" => Ok(hash_str(args).to_string()),
+            "box" => {
+                let idx: u8 = args.parse().context("expected a box number in 0..=255")?;
+                let lenses = &self.final_boxes[idx as usize];
+                if lenses.is_empty() {
+                    return Ok(format!("box {idx} is empty"));
+                }
+                Ok(lenses
+                    .iter()
+                    .map(|(label, fc)| format!("[{label} {fc}]"))
+                    .collect::<Vec<_>>()
+                    .join(" "))
+            }
+            _ => unreachable!(),
+        }
+    }
 }
 
 enum Op<'s> {
@@ -1,4 +1,11 @@
-use std::fs;
+use std::{error::Error, fs};
+
+use advent_of_code_2023::{
+    explore::{self, Command, Explore},
+    playback::{Playback, Step},
+    render::{Color, Frame, Style, Target},
+};
+use anyhow::Context;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 struct Pos(usize, usize);
@@ -53,29 +60,140 @@ impl From<char> for Tile {
     }
 }
 
-fn count_energized_tiles(grid: &[Tile], width: usize, start: Pos, dir: Direction) -> usize {
+impl From<Tile> for char {
+    fn from(value: Tile) -> Self {
+        match value {
+            Tile::Empty => '.',
+            Tile::MirrorUR => '/',
+            Tile::MirrorDR => '\\',
+            Tile::SplitterVertical => '|',
+            Tile::SplitterHorizontal => '-',
+        }
+    }
+}
+
+/// Directions a beam leaves a tile in, when entering it going `dir`
+fn bounce(tile: Tile, dir: Direction) -> &'static [Direction] {
     use Direction::*;
     use Tile::*;
+    match (tile, dir) {
+        (MirrorDR, Left) | (MirrorUR, Right) => &[Up],
+        (MirrorDR, Right) | (MirrorUR, Left) => &[Down],
+        (MirrorDR, Up) | (MirrorUR, Down) => &[Left],
+        (MirrorDR, Down) | (MirrorUR, Up) => &[Right],
+        (SplitterHorizontal, Up | Down) => &[Right, Left],
+        (SplitterVertical, Left | Right) => &[Up, Down],
+        (_, Up) => &[Up],
+        (_, Down) => &[Down],
+        (_, Left) => &[Left],
+        (_, Right) => &[Right],
+    }
+}
+
+/// Beams advancing one tile per step, for playback. Same rules as [`energize`], but breadth-first.
+struct BeamFront<'g> {
+    grid: &'g [Tile],
+    width: usize,
+    visited: Vec<u8>,
+    front: Vec<(Pos, Direction)>,
+}
+
+impl<'g> BeamFront<'g> {
+    fn new(grid: &'g [Tile], width: usize, start: Pos, dir: Direction) -> Self {
+        let mut visited = vec![0; grid.len()];
+        visited[start.1 * width + start.0] |= dir as u8;
+        Self {
+            grid,
+            width,
+            visited,
+            front: vec![(start, dir)],
+        }
+    }
+}
+
+impl Step for BeamFront<'_> {
+    fn step(&mut self) -> bool {
+        let (width, height) = (self.width, self.grid.len() / self.width);
+        for (pos, dir) in std::mem::take(&mut self.front) {
+            for &d in bounce(self.grid[pos.1 * width + pos.0], dir) {
+                let Some(p) = pos.translate(d, width, height) else {
+                    continue;
+                };
+                let visited = &mut self.visited[p.1 * width + p.0];
+                if *visited & d as u8 == 0 {
+                    *visited |= d as u8;
+                    self.front.push((p, d));
+                }
+            }
+        }
+        !self.front.is_empty()
+    }
+
+    fn frame(&self) -> Frame {
+        let mut frame = frame(self.grid, self.width, &self.visited);
+        frame.highlight(self.front.iter().map(|(p, _)| (p.0, p.1)), Color::Red);
+        frame
+    }
+}
+
+/// The grid, with the energized tiles (`visited` not zero) highlighted
+fn frame(grid: &[Tile], width: usize, visited: &[u8]) -> Frame {
+    let mut frame = Frame::new(width, grid.len() / width, |x, y| grid[y * width + x].into());
+    frame.style_with(|_, _, c| match c {
+        '.' => Style::default(),
+        _ => Style::fg(Color::Cyan),
+    });
+    frame.highlight(
+        (0..grid.len())
+            .filter(|&i| visited[i] != 0)
+            .map(|i| (i % width, i / width)),
+        Color::Yellow,
+    );
+    frame
+}
+
+fn count_energized_tiles(grid: &[Tile], width: usize, start: Pos, dir: Direction) -> usize {
+    energize(grid, width, start, dir)
+        .iter()
+        .filter(move |&&b| b != 0)
+        .count()
+}
+
+/// Whether a beam can enter the grid at `start` going `dir`: it must be on an edge, pointing
+/// inwards.
+fn is_entry(start: Pos, dir: Direction, width: usize, height: usize) -> bool {
+    use Direction::*;
+
+    start.fit(width, height).is_some()
+        // On any edge/corner
+        && (start.0 == 0 || start.0 == width - 1 || start.1 == 0 || start.1 == height - 1)
+        // Top-left corner
+        && (!(start.1 == 0 && start.0 == 0) || (dir == Down || dir == Right))
+        // Top-right corner
+        && (!(start.1 == 0 && start.0 == width - 1) || (dir == Down || dir == Left))
+        // Bottom-left corner
+        && (!(start.1 == height - 1 && start.0 == 0) || (dir == Up || dir == Right))
+        // Bottom-right corner
+        && (!(start.1 == height - 1 && start.0 == width - 1) || (dir == Up || dir == Left))
+        // Top edge
+        && (!(start.1 == 0 && start.0 > 0 && start.0 < width - 1) || dir == Down)
+        // Bottom edge
+        && (!(start.1 == height - 1 && start.0 > 0 && start.0 < width - 1) || dir == Up)
+        // Left edge
+        && (!(start.0 == 0 && start.1 > 0 && start.1 < height - 1) || dir == Right)
+        // Right edge
+        && (!(start.0 == width - 1 && start.1 > 0 && start.1 < height - 1) || dir == Left)
+}
+
+/// Returns the bitmap of the directions each tile has been crossed in (see [`Direction`]).
+/// A tile is energized if it's not zero.
+fn energize(grid: &[Tile], width: usize, start: Pos, dir: Direction) -> Vec<u8> {
     let height = grid.len() / width;
 
-    // On any edge/corner
-    assert!(start.0 == 0 || start.0 == width - 1 || start.1 == 0 || start.1 == height - 1);
-    // Top-left corner
-    assert!(!(start.1 == 0 && start.0 == 0) || (dir == Down || dir == Right));
-    // Top-right corner
-    assert!(!(start.1 == 0 && start.0 == width - 1) || (dir == Down || dir == Left));
-    // Bottom-left corner
-    assert!(!(start.1 == height - 1 && start.0 == 0) || (dir == Up || dir == Right));
-    // Bottom-right corner
-    assert!(!(start.1 == height - 1 && start.0 == width - 1) || (dir == Up || dir == Left));
-    // Top edge
-    assert!(!(start.1 == 0 && start.0 > 0 && start.0 < width - 1) || dir == Down);
-    // Bottom edge
-    assert!(!(start.1 == height - 1 && start.0 > 0 && start.0 < width - 1) || dir == Up);
-    // Left edge
-    assert!(!(start.0 == 0 && start.1 > 0 && start.1 < height - 1) || dir == Right);
-    // Right edge
-    assert!(!(start.0 == width - 1 && start.1 > 0 && start.1 < height - 1) || dir == Left);
+    assert!(
+        is_entry(start, dir, width, height),
+        "{start:?} going {dir:?} does not enter the grid from an edge"
+    );
 
     fn recurse(
         grid: &[Tile],
@@ -99,51 +217,11 @@ fn count_energized_tiles(grid: &[Tile], width: usize, start: Pos, dir: Direction
 
         visited[idx] |= dir as u8;
 
-        let _ = match (grid[idx], dir) {
-            (MirrorDR, Left) | (MirrorUR, Right) => {
-                recurse(grid, visited, width, pos.translate(Up, width, height)?, Up)
-            }
-            (MirrorDR, Right) | (MirrorUR, Left) => recurse(
-                grid,
-                visited,
-                width,
-                pos.translate(Down, width, height)?,
-                Down,
-            ),
-            (MirrorDR, Up) | (MirrorUR, Down) => recurse(
-                grid,
-                visited,
-                width,
-                pos.translate(Left, width, height)?,
-                Left,
-            ),
-            (MirrorDR, Down) | (MirrorUR, Up) => recurse(
-                grid,
-                visited,
-                width,
-                pos.translate(Right, width, height)?,
-                Right,
-            ),
-            (SplitterHorizontal, Up) | (SplitterHorizontal, Down) => {
-                if let Some(p) = pos.translate(Right, width, height) {
-                    recurse(grid, visited, width, p, Right);
-                }
-                if let Some(p) = pos.translate(Left, width, height) {
-                    recurse(grid, visited, width, p, Left);
-                }
-                Some(())
-            }
-            (SplitterVertical, Left) | (SplitterVertical, Right) => {
-                if let Some(p) = pos.translate(Up, width, height) {
-                    recurse(grid, visited, width, p, Up);
-                }
-                if let Some(p) = pos.translate(Down, width, height) {
-                    recurse(grid, visited, width, p, Down);
-                }
-                Some(())
+        for &d in bounce(grid[idx], dir) {
+            if let Some(p) = pos.translate(d, width, height) {
+                recurse(grid, visited, width, p, d);
             }
-            (_, d) => recurse(grid, visited, width, pos.translate(d, width, height)?, d),
-        };
+        }
 
         Some(())
     }
@@ -151,10 +229,10 @@ fn count_energized_tiles(grid: &[Tile], width: usize, start: Pos, dir: Direction
     let mut visited = vec![0; grid.len()];
 
     recurse(grid, &mut visited, width, start, dir);
-    visited.iter().filter(move |&&b| b != 0).count()
+    visited
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
     use Direction::*;
     let content = fs::read_to_string("inputs/p16/tiles.txt").expect("reading problem input");
 
@@ -171,6 +249,15 @@ fn main() {
     let part1 = count_energized_tiles(&grid, width, Pos(0, 0), Direction::Right);
     println!("[PART 1] Energized tiles: {part1}");
 
+    if let Some(target) = Target::from_args("p16")? {
+        let energized = energize(&grid, width, Pos(0, 0), Direction::Right);
+        frame(&grid, width, &energized).render(&target, "energized")?;
+    }
+    if let Some(playback) = Playback::from_args("p16")? {
+        let mut beams = BeamFront::new(&grid, width, Pos(0, 0), Direction::Right);
+        playback.run(&mut beams, "beams")?;
+    }
+
     // find entry point & direction with max coverage
     let entries = (0..width)
         .map(|x| (Pos(x, 0), Down))
@@ -182,4 +269,53 @@ fn main() {
         .max()
         .unwrap();
     println!("[PART 2] Maximum energized tiles: {part2}");
+
+    if explore::requested() {
+        explore::repl("p16", &mut Explorer { grid, width })?;
+    }
+
+    Ok(())
+}
+
+struct Explorer {
+    grid: Vec<Tile>,
+    width: usize,
+}
+
+impl Explore for Explorer {
+    const COMMANDS: &'static [Command] = &[Command {
+        name: "energize",
+        usage: "energize <x> <y> <dir>",
+        help: "shoots a beam from an edge (dir: up, down, left, right)",
+    }];
+
+    fn run(&mut self, _name: &str, args: &str) -> anyhow::Result<String> {
+        let usage = "expected `energize <x> <y> <up|down|left|right>`";
+        let [x, y, dir] = args
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!(usage))?;
+        let start = Pos(x.parse().context(usage)?, y.parse().context(usage)?);
+        let dir = match dir {
+            "up" | "U" => Direction::Up,
+            "down" | "D" => Direction::Down,
+            "left" | "L" => Direction::Left,
+            "right" | "R" => Direction::Right,
+            _ => anyhow::bail!(usage),
+        };
+
+        let height = self.grid.len() / self.width;
+        anyhow::ensure!(
+            is_entry(start, dir, self.width, height),
+            "the beam must start on an edge of the {}x{height} grid, pointing inwards",
+            self.width
+        );
+        let visited = energize(&self.grid, self.width, start, dir);
+        Ok(format!(
+            "{}{} tiles energized",
+            frame(&self.grid, self.width, &visited).text(true),
+            visited.iter().filter(|&&b| b != 0).count()
+        ))
+    }
 }
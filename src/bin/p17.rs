@@ -1,4 +1,6 @@
-use std::{collections::VecDeque, fs, ops::RangeInclusive};
+use std::{collections::VecDeque, error::Error, fs, ops::RangeInclusive};
+
+use advent_of_code_2023::render::{Color, Frame, Style, Target};
 
 use Direction::*;
 
@@ -37,7 +39,7 @@ impl Direction {
     }
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
     // Can move at most 3 times in one direction.
     // Map is heat loss per cell.
     // From top-left to bottom-right corner, find valid path
@@ -51,23 +53,41 @@ fn main() {
         .collect();
     let height = grid.len() / width;
 
-    let part1 = it_is_horrible(&grid, width, height, 1..=3);
+    let render = Target::from_args("p17")?;
+
+    let (part1, path1) = it_is_horrible(&grid, width, height, 1..=3);
     println!("[PART 1] Min cost to reach destination: {part1}");
-    let part2 = it_is_horrible(&grid, width, height, 4..=10);
+    let (part2, path2) = it_is_horrible(&grid, width, height, 4..=10);
     println!("[PART 2] Min cost to reach destination: {part2}");
 
     // Now literally same problem but with different constraints.... (has to move at least 4 blocks
     // straight, at most 10)
     // Too lazy to make the solution generic, so here we go...
+
+    if let Some(target) = render {
+        for (name, path) in [("part1", path1), ("part2", path2)] {
+            let mut frame = Frame::new(width, height, |x, y| {
+                char::from_digit(grid[x + y * width] as u32, 10).unwrap()
+            });
+            frame.style_with(|_, _, _| Style::fg(Color::BrightBlack));
+            frame.path(path, Color::Red);
+            frame.render(&target, name)?;
+        }
+    }
+
+    Ok(())
 }
 
 /// Takes the grid and its dimension. Also it takes a range of moves, where the minimum
+/// is the amount of straight moves before being allowed to turn, and the maximum the amount
+/// before being forced to.
+/// Returns the minimum cost and the cells of a path achieving it, from the top-left corner.
 fn it_is_horrible(
     grid: &[u8],
     width: usize,
     height: usize,
     can_move: RangeInclusive<usize>,
-) -> u32 {
+) -> (u32, Vec<(usize, usize)>) {
     // now, the thing is..
     // I need to keep track of the cost of reaching each tile. Also, i need to know what move
     // (direction) led to this result. The algorithm is: on a tile, that has been reached by moving
@@ -78,6 +98,8 @@ fn it_is_horrible(
         x + y * width + (dir as u8 as usize) * (width * height)
     };
     let mut dp = vec![u32::MAX; grid.len() * 4];
+    // state (x, y, dir) each state was last improved from, to rebuild the path at the end
+    let mut prev = vec![None; grid.len() * 4];
     [Right, Down]
         .into_iter()
         .for_each(|dir| dp[idx(0, 0, dir)] = 0 as _); // heat loss happens when ENTERING a tile!
@@ -101,13 +123,12 @@ fn it_is_horrible(
             let mut cost = dp[idx(x, y, dir)]; // we were NOT moving in direction `d`!
             for m in 1..=*can_move.end() {
                 if let Some(new_pos) = d.translate(1, pos, width, height) {
-                    cost = cost + grid[new_pos.0 + new_pos.1 * width] as u32;
+                    cost += grid[new_pos.0 + new_pos.1 * width] as u32;
                     // move at least X distance before turning, see part 2
-                    if can_move.contains(&m) {
-                        if dp[idx(new_pos.0, new_pos.1, d)] > cost {
-                            dp[idx(new_pos.0, new_pos.1, d)] = cost;
-                            q.push_back((new_pos.0, new_pos.1, d));
-                        }
+                    if can_move.contains(&m) && dp[idx(new_pos.0, new_pos.1, d)] > cost {
+                        dp[idx(new_pos.0, new_pos.1, d)] = cost;
+                        prev[idx(new_pos.0, new_pos.1, d)] = Some((x, y, dir));
+                        q.push_back((new_pos.0, new_pos.1, d));
                     }
                     pos = new_pos;
                 } else {
@@ -121,9 +142,25 @@ fn it_is_horrible(
     //     println!("{row:?}");
     // }
 
-    [Down, Right]
+    let (cost, end_dir) = [Down, Right]
         .into_iter()
-        .map(|d| dp[idx(width - 1, height - 1, d)])
-        .min()
-        .unwrap()
+        .map(|d| (dp[idx(width - 1, height - 1, d)], d))
+        .min_by_key(|&(cost, _)| cost)
+        .unwrap();
+
+    // walk back the straight segments, filling in the cells in between
+    let mut path = vec![(width - 1, height - 1)];
+    let mut state = (width - 1, height - 1, end_dir);
+    while let Some(p) = prev[idx(state.0, state.1, state.2)] {
+        let back = state.2.opposite();
+        let mut pos = (state.0, state.1);
+        while pos != (p.0, p.1) {
+            pos = back.translate(1, pos, width, height).unwrap();
+            path.push(pos);
+        }
+        state = p;
+    }
+    path.reverse();
+
+    (cost, path)
 }
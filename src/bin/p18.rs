@@ -178,51 +178,28 @@ fn produce_edges(motions: &[Motion]) -> Vec<Edge> {
 fn print_edges(edges_1: &[Edge], edges_2: &[Edge]) -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(feature = "plotters")]
     {
-        use plotters::prelude::*;
+        use advent_of_code_2023::render::{self, Color};
+
         let path = format!(
             "./inputs/p18/edges-{}-{}.png",
             edges_1.iter().map(Edge::l1_norm).sum::<usize>(),
             edges_2.iter().map(Edge::l1_norm).sum::<usize>(),
         );
-        let root = BitMapBackend::new(&path, (400, 400)).into_drawing_area();
-
-        root.fill(&WHITE)?;
-
-        let min_x = edges_1.iter().map(|e| e.s().0).min().unwrap();
-        let max_x = edges_1.iter().map(|e| e.s().0).max().unwrap();
-        let max_y = -edges_1.iter().map(|e| e.s().1).min().unwrap();
-        let min_y = -edges_1.iter().map(|e| e.s().1).max().unwrap();
-        let mut chart = ChartBuilder::on(&root)
-            .margin(20)
-            .x_label_area_size(10)
-            .y_label_area_size(10)
-            .build_cartesian_2d(min_x..max_x, min_y..max_y)?;
-
-        chart
-            .configure_mesh()
-            .disable_x_mesh()
-            .disable_y_mesh()
-            .draw()?;
-
-        let plotting_area = chart.plotting_area();
-
-        plotting_area.draw(&PathElement::new(
-            edges_1
-                .iter()
-                .chain(Some(&edges_1[0]))
-                .map(|e| (e.s().0, -e.s().1))
-                .collect::<Vec<_>>(),
-            RED.mix(0.5),
-        ))?;
-        plotting_area.draw(&PathElement::new(
-            edges_2
+        // the digger's y grows downwards
+        let outline = |edges: &[Edge]| {
+            edges
                 .iter()
-                .chain(Some(&edges_2[0]))
-                .map(|e| (e.s().0, -e.s().1))
-                .collect::<Vec<_>>(),
-            GREEN.mix(0.5),
-        ))?;
-        root.present()?;
+                .map(|e| (e.s().0 as i64, -e.s().1 as i64))
+                .collect::<Vec<_>>()
+        };
+        render::polygons(
+            path.as_ref(),
+            (400, 400),
+            &[
+                (outline(edges_1), Color::Red),
+                (outline(edges_2), Color::Green),
+            ],
+        )?;
     }
     Ok(())
 }
@@ -283,7 +260,7 @@ fn parse_line(s: &str) -> (Motion, Motion) {
     };
     let len_1 = elems.next().unwrap().parse().expect("parsing line length");
 
-    let color = usize::from_str_radix(elems.next().unwrap().trim_matches(&['(', ')', '#']), 16)
+    let color = usize::from_str_radix(elems.next().unwrap().trim_matches(['(', ')', '#']), 16)
         .expect("parsing color");
     let dir_2 = [Dir::Right, Dir::Down, Dir::Left, Dir::Up][color & 0b1111];
     let len_2 = color >> 4;
@@ -1,5 +1,7 @@
 use std::{collections::HashMap, fs, ops::RangeInclusive};
 
+use advent_of_code_2023::explore::{self, Command, Explore};
+use anyhow::Context;
 use itertools::Itertools;
 
 #[derive(Debug, Clone, Copy)]
@@ -32,6 +34,7 @@ impl Default for PartRange {
 }
 
 impl PartRange {
+    #[allow(clippy::reversed_empty_ranges)]
     const EMPTY: Self = Self {
         m: 1..=0,
         a: 1..=0,
@@ -138,34 +141,35 @@ struct Part {
     s: u16,
 }
 
-fn parse_line(s: &str) -> Result<Workflow, Part> {
+fn parse_line(s: &str) -> Result<Workflow<'_>, Part> {
     let (rule_name, content) = s.split_once('{').unwrap();
     let (content, _) = content.split_once('}').unwrap();
 
     if rule_name.is_empty() {
-        Err(parse_part(content))
+        Err(parse_part(content).expect("parsing part"))
     } else {
         Ok(parse_workflow(rule_name, content))
     }
 }
 
-fn parse_part(c: &str) -> Part {
+/// Parses the content of `{x=1,m=2,a=3,s=4}` (without braces). Missing ratings are zero.
+fn parse_part(c: &str) -> Option<Part> {
     let set_kv = |mut part: Part, kv: &str| {
-        let (k, v) = kv.split_once('=').unwrap();
-        let v = v.parse().unwrap();
-        match k {
+        let (k, v) = kv.split_once('=')?;
+        let v = v.trim().parse().ok()?;
+        match k.trim() {
             "x" => part.x = v,
             "a" => part.a = v,
             "m" => part.m = v,
             "s" => part.s = v,
-            _ => unreachable!(),
+            _ => return None,
         }
-        part
+        Some(part)
     };
-    c.split(',').fold(Part::default(), set_kv)
+    c.split(',').try_fold(Part::default(), set_kv)
 }
 
-fn parse_rule(rule: &str) -> Rule {
+fn parse_rule(rule: &str) -> Rule<'_> {
     use Condition::*;
     match rule.split_once(':') {
         Some((cond, next)) => {
@@ -221,27 +225,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .map(|v| v.map(|w| (w.name, w)))
         .partition_result();
 
-    let mut part1 = 0u64;
-
-    for p in parts {
-        // Walk the workflow graph (is it a tree?)
-        let mut w = workflows
-            .get("in")
-            .expect("\"in\" workflow must be present in input"); // has to be there
-        'part: loop {
-            for rule in &w.rules {
-                if rule.cond.matches(p) {
-                    match rule.next {
-                        State::Accept => part1 += p.a as u64 + p.x as u64 + p.s as u64 + p.m as u64,
-                        State::Reject => (),
-                        State::Other(next_name) => {
-                            w = workflows.get(next_name).unwrap();
-                            continue 'part;
-                        }
-                    }
-                    break 'part;
-                }
-            }
+    let mut part1 = 0;
+    for &p in &parts {
+        if evaluate(&workflows, p)?.0 {
+            part1 += p.a as u64 + p.x as u64 + p.s as u64 + p.m as u64;
         }
     }
 
@@ -259,9 +246,89 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("[PART2] Possible arrangements: {arrangements}");
 
+    if explore::requested() {
+        explore::repl("p19", &mut Explorer { workflows })?;
+    }
+
     Ok(())
 }
 
+/// Walks the workflow graph (is it a tree?) starting from `in`. Returns whether the part is
+/// accepted, and the names of the workflows it went through.
+fn evaluate<'s>(
+    workflows: &HashMap<&str, Workflow<'s>>,
+    p: Part,
+) -> anyhow::Result<(bool, Vec<&'s str>)> {
+    let mut w = workflows
+        .get("in")
+        .context("\"in\" workflow must be present in input")?;
+    let mut path = vec![w.name];
+    'part: loop {
+        for rule in &w.rules {
+            if rule.cond.matches(p) {
+                match rule.next {
+                    State::Accept => return Ok((true, path)),
+                    State::Reject => return Ok((false, path)),
+                    State::Other(next_name) => {
+                        w = workflows.get(next_name).with_context(|| {
+                            format!("workflow {} leads to unknown {next_name}", w.name)
+                        })?;
+                        path.push(w.name);
+                        continue 'part;
+                    }
+                }
+            }
+        }
+        unreachable!("workflow {} has no rule matching {p:?}", w.name);
+    }
+}
+
+struct Explorer<'s> {
+    workflows: HashMap<&'s str, Workflow<'s>>,
+}
+
+impl Explore for Explorer<'_> {
+    const COMMANDS: &'static [Command] = &[
+        Command {
+            name: "eval",
+            usage: "eval {x=..,m=..,a=..,s=..}",
+            help: "runs a part through the workflows",
+        },
+        Command {
+            name: "show",
+            usage: "show <workflow>",
+            help: "prints the rules of a workflow",
+        },
+    ];
+
+    fn run(&mut self, name: &str, args: &str) -> anyhow::Result<String> {
+        match name {
+            "eval" => {
+                let part = args
+                    .strip_prefix('{')
+                    .and_then(|a| a.strip_suffix('}'))
+                    .and_then(parse_part)
+                    .context("expected a part like {x=787,m=2655,a=1222,s=2876}")?;
+                let (accepted, path) = evaluate(&self.workflows, part)?;
+                let rating = part.a as u64 + part.x as u64 + part.s as u64 + part.m as u64;
+                Ok(format!(
+                    "{} -> {} (rating {rating})",
+                    path.join(" -> "),
+                    if accepted { "A" } else { "R" }
+                ))
+            }
+            "show" => {
+                let w = self.workflows.get(args).context("no such workflow")?;
+                Ok(w.rules
+                    .iter()
+                    .map(|r| format!("  {:?} => {:?}", r.cond, r.next))
+                    .join("\n"))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
 fn walk_graph(ws: &HashMap<&str, Workflow>, pr: PartRange, w: &Workflow<'_>) -> u64 {
     let arrangements = |pr: &PartRange| {
         (pr.a.end() + 1 - pr.a.start()) as u64
@@ -1,16 +1,141 @@
-use std::fs;
+use std::{collections::BTreeMap, fs};
 
-use pest::Parser;
+use anyhow::{bail, Context};
+use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
+use serde::Serialize;
 
 #[derive(Parser)]
 #[grammar = "bin/p2.pest"]
 struct GameParser;
 
-fn main() {
+type GameId = u32;
+
+/// Count of cubes of each colour, in a draw or in a bag
+type Cubes<'s> = BTreeMap<&'s str, u32>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Game<'s> {
+    id: GameId,
+    draws: Vec<Cubes<'s>>,
+}
+
+/// Queries given on the command line
+#[derive(Debug, Default)]
+struct Queries {
+    /// Bags given with `--bag=<draw>` or, one per line, in `--bags=<file>`. Written like a draw,
+    /// e.g. `12 red, 13 green, 14 blue`.
+    bags: Vec<String>,
+    /// Sets of games given with `--smallest=<id>,<id>,...`, to find the smallest bag of
+    smallest: Vec<Vec<GameId>>,
+    /// `--min-bags` lists the smallest bag of each game
+    min_bags: bool,
+    /// `--stats[=json]` prints [`Stats`] instead, as a table or as JSON
+    stats: Option<bool>,
+    /// `--threshold=N` reports the games that need a bag of more than N cubes in the stats
+    threshold: Option<u32>,
+}
+
+impl Queries {
+    fn from_args() -> anyhow::Result<Self> {
+        let mut queries = Self::default();
+        for arg in std::env::args().skip(1) {
+            let (key, value) = arg.split_once('=').unwrap_or((&arg, ""));
+            match key {
+                "--bag" => queries.bags.push(value.to_string()),
+                "--bags" => {
+                    let file =
+                        fs::read_to_string(value).with_context(|| format!("reading {value}"))?;
+                    queries.bags.extend(
+                        file.lines()
+                            .map(str::trim)
+                            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                            .map(str::to_string),
+                    );
+                }
+                "--smallest" => queries.smallest.push(
+                    value
+                        .split(',')
+                        .map(|id| id.trim().parse())
+                        .collect::<Result<_, _>>()
+                        .with_context(|| {
+                            format!("expected `--smallest=<id>,<id>,...`, got {arg:?}")
+                        })?,
+                ),
+                "--min-bags" => queries.min_bags = true,
+                "--stats" => {
+                    queries.stats = Some(match value {
+                        "" => false,
+                        "json" => true,
+                        _ => bail!("expected `--stats[=json]`, got {arg:?}"),
+                    })
+                }
+                "--threshold" => {
+                    queries.threshold =
+                        Some(value.parse().with_context(|| {
+                            format!("expected `--threshold=<cubes>`, got {arg:?}")
+                        })?)
+                }
+                _ => (),
+            }
+        }
+        Ok(queries)
+    }
+}
+
+fn main() -> anyhow::Result<()> {
     let content = fs::read_to_string("inputs/p2/games.txt").expect("reading file");
+    let games = parse_games(&content);
+    let queries = Queries::from_args()?;
+
+    let mut pp = vec![Cubes::from([("red", 12), ("green", 13), ("blue", 14)])];
+    if !queries.bags.is_empty() {
+        pp = queries
+            .bags
+            .iter()
+            .map(|spec| parse_bag(spec).with_context(|| format!("parsing bag {spec:?}")))
+            .collect::<anyhow::Result<_>>()?;
+    }
+
+    if let Some(json) = queries.stats {
+        let stats = Stats::new(&games, &pp, queries.threshold);
+        if json {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+        } else {
+            stats.print();
+        }
+        return Ok(());
+    }
+
+    for bag in &pp {
+        let possible: Vec<_> = possible_games(&games, bag).collect();
+        println!(
+            "ID-sum of possible games with {}: {}",
+            format_cubes(bag),
+            possible.iter().sum::<u32>()
+        );
+        println!("  games: {possible:?}");
+    }
+
+    // Find sum of power for all games
+    let power_sum: u32 = games.iter().map(|game| min_power(&game.draws)).sum();
+    println!("Power sum of all games: {power_sum}");
 
-    let file = GameParser::parse(Rule::file, &content)
+    if queries.min_bags {
+        for game in &games {
+            println!("Game {}: {}", game.id, format_cubes(&min_bag(&game.draws)));
+        }
+    }
+    for ids in &queries.smallest {
+        let bag = smallest_bag(&games, ids)?;
+        println!("Smallest bag for games {ids:?}: {}", format_cubes(&bag));
+    }
+
+    Ok(())
+}
+
+fn parse_games(content: &str) -> Vec<Game<'_>> {
+    let file = GameParser::parse(Rule::file, content)
         .expect("parsing file")
         .next()
         .unwrap();
@@ -21,67 +146,322 @@ fn main() {
         }
 
         let mut game_iter = game.into_inner();
-        let game_id: u32 = game_iter.next().unwrap().as_str().parse().unwrap();
-        let draws: Vec<[u32; 3]> = game_iter
-            .map(|draw| {
-                let mut draw_count = [0, 0, 0]; // r,g,b
-                for colorcount in draw.into_inner() {
-                    let mut colorcount_iter = colorcount.into_inner();
-                    let count: u32 = colorcount_iter.next().unwrap().as_str().parse().unwrap();
-                    let color = colorcount_iter.next().unwrap().as_str();
-                    match color {
-                        "red" => draw_count[0] = count,
-                        "green" => draw_count[1] = count,
-                        "blue" => draw_count[2] = count,
-                        _ => unreachable!(),
+        let id: GameId = game_iter.next().unwrap().as_str().parse().unwrap();
+        let draws = game_iter.map(parse_draw).collect();
+        games.push(Game { id, draws });
+    }
+    games
+}
+
+fn parse_draw(draw: Pair<'_, Rule>) -> Cubes<'_> {
+    let mut draw_count = Cubes::new();
+    for colorcount in draw.into_inner() {
+        let mut colorcount_iter = colorcount.into_inner();
+        let count: u32 = colorcount_iter.next().unwrap().as_str().parse().unwrap();
+        let color = colorcount_iter.next().unwrap().as_str();
+        *draw_count.entry(color).or_default() += count;
+    }
+    draw_count
+}
+
+/// Parses a bag written like a draw, e.g. `12 red, 13 green, 14 blue`
+fn parse_bag(spec: &str) -> Result<Cubes<'_>, Box<pest::error::Error<Rule>>> {
+    let bag = GameParser::parse(Rule::bag, spec.trim())?.next().unwrap();
+    Ok(parse_draw(bag.into_inner().next().unwrap()))
+}
+
+/// `blue=14 green=13 red=12` listing of the cubes
+fn format_cubes(cubes: &Cubes) -> String {
+    let counts: Vec<_> = cubes.iter().map(|(c, n)| format!("{c}={n}")).collect();
+    counts.join(" ")
+}
+
+/// Smallest bag the draws could have been taken from: the maximum count of each colour
+fn min_bag<'s>(draws: &[Cubes<'s>]) -> Cubes<'s> {
+    draws.iter().fold(Cubes::new(), |mut bag, draw| {
+        for (&color, &count) in draw {
+            let max = bag.entry(color).or_default();
+            *max = count.max(*max);
+        }
+        bag
+    })
+}
+
+/// Smallest bag that makes all the given games possible
+fn smallest_bag<'s>(games: &[Game<'s>], ids: &[GameId]) -> anyhow::Result<Cubes<'s>> {
+    let mut draws = vec![];
+    for id in ids {
+        let game = games
+            .iter()
+            .find(|g| g.id == *id)
+            .with_context(|| format!("no game {id}"))?;
+        draws.extend(game.draws.iter().cloned());
+    }
+    Ok(min_bag(&draws))
+}
+
+/// Min power of the set for this game, over the colours that appear in it
+fn min_power(draws: &[Cubes]) -> u32 {
+    min_bag(draws).values().product()
+}
+
+/// Returns an iterator on the IDs of the games that are possible with the given bag. Colours
+/// missing from the bag have no cubes.
+fn possible_games<'g>(games: &'g [Game], bag: &'g Cubes) -> impl Iterator<Item = GameId> + 'g {
+    games.iter().filter_map(move |game| {
+        if game.draws.iter().all(|d| {
+            d.iter()
+                .all(|(c, n)| *n <= bag.get(c).copied().unwrap_or(0))
+        }) {
+            Some(game.id)
+        } else {
+            None
+        }
+    })
+}
+
+/// Statistics over all the games, for `--stats`
+#[derive(Debug, PartialEq, Serialize)]
+struct Stats<'s> {
+    colors: BTreeMap<&'s str, ColorStats>,
+    /// Draws that make games impossible, for each bag
+    impossible: Vec<ImpossibleDraws<'s>>,
+    /// Games that need a bag of more than `threshold` cubes
+    anomalies: Vec<Anomaly<'s>>,
+    threshold: Option<u32>,
+}
+
+/// Distribution of the counts of a colour, over the draws it appears in
+#[derive(Debug, PartialEq, Serialize)]
+struct ColorStats {
+    draws: usize,
+    total: u32,
+    min: u32,
+    max: u32,
+    mean: f64,
+    /// Number of draws with each count
+    histogram: BTreeMap<u32, usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct ImpossibleDraws<'s> {
+    bag: Cubes<'s>,
+    draws: Vec<ImpossibleDraw<'s>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct ImpossibleDraw<'s> {
+    game: GameId,
+    /// Index of the draw in the game
+    draw: usize,
+    /// Colours the bag does not have enough of, and how many are missing
+    missing: Cubes<'s>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Anomaly<'s> {
+    game: GameId,
+    min_bag: Cubes<'s>,
+    cubes: u32,
+}
+
+impl<'s> Stats<'s> {
+    fn new(games: &[Game<'s>], bags: &[Cubes<'s>], threshold: Option<u32>) -> Self {
+        let mut counts: BTreeMap<&str, Vec<u32>> = BTreeMap::new();
+        for (&color, &count) in games.iter().flat_map(|g| &g.draws).flatten() {
+            counts.entry(color).or_default().push(count);
+        }
+        let colors = counts
+            .into_iter()
+            .map(|(color, counts)| {
+                let total = counts.iter().sum();
+                let mut histogram = BTreeMap::new();
+                for &c in &counts {
+                    *histogram.entry(c).or_default() += 1;
+                }
+                let stats = ColorStats {
+                    draws: counts.len(),
+                    total,
+                    min: *counts.iter().min().unwrap(),
+                    max: *counts.iter().max().unwrap(),
+                    mean: total as f64 / counts.len() as f64,
+                    histogram,
+                };
+                (color, stats)
+            })
+            .collect();
+
+        let impossible = bags
+            .iter()
+            .map(|bag| {
+                let mut draws = vec![];
+                for game in games {
+                    for (i, draw) in game.draws.iter().enumerate() {
+                        let missing: Cubes = draw
+                            .iter()
+                            .filter_map(|(&c, &n)| {
+                                let available = bag.get(c).copied().unwrap_or(0);
+                                (n > available).then(|| (c, n - available))
+                            })
+                            .collect();
+                        if !missing.is_empty() {
+                            draws.push(ImpossibleDraw {
+                                game: game.id,
+                                draw: i,
+                                missing,
+                            });
+                        }
                     }
                 }
-                draw_count
+                ImpossibleDraws {
+                    bag: bag.clone(),
+                    draws,
+                }
             })
             .collect();
-        games.push((game_id, draws));
-    }
 
-    let pp = [(12, 13, 14)];
+        let anomalies = threshold.map_or(vec![], |threshold| {
+            games
+                .iter()
+                .map(|game| {
+                    let min_bag = min_bag(&game.draws);
+                    Anomaly {
+                        game: game.id,
+                        cubes: min_bag.values().sum(),
+                        min_bag,
+                    }
+                })
+                .filter(|a| a.cubes > threshold)
+                .collect()
+        });
+
+        Self {
+            colors,
+            impossible,
+            anomalies,
+            threshold,
+        }
+    }
 
-    for (r, g, b) in pp {
+    fn print(&self) {
         println!(
-            "ID-sum of possible games with r={r} g={g} b={b}: {}",
-            possible_games(&games, r, g, b).sum::<u32>()
+            "{:<12} {:>6} {:>7} {:>4} {:>4} {:>6}  histogram (count:draws)",
+            "colour", "draws", "total", "min", "max", "mean"
         );
-    }
+        for (color, s) in &self.colors {
+            let histogram: Vec<_> = s
+                .histogram
+                .iter()
+                .map(|(c, n)| format!("{c}:{n}"))
+                .collect();
+            println!(
+                "{color:<12} {:>6} {:>7} {:>4} {:>4} {:>6.2}  {}",
+                s.draws,
+                s.total,
+                s.min,
+                s.max,
+                s.mean,
+                histogram.join(" ")
+            );
+        }
 
-    // Find sum of power for all games
-    let power_sum: u32 = games.iter().map(|(_game_id, draws)| min_power(draws)).sum();
-    println!("Power sum of all games: {power_sum}");
+        for bag in &self.impossible {
+            println!("\nImpossible draws with {}:", format_cubes(&bag.bag));
+            for d in &bag.draws {
+                let missing: Vec<_> = d.missing.iter().map(|(c, n)| format!("{c} +{n}")).collect();
+                println!("  game {} draw {}: {}", d.game, d.draw, missing.join(", "));
+            }
+        }
+
+        if let Some(threshold) = self.threshold {
+            println!("\nGames needing more than {threshold} cubes:");
+            for a in &self.anomalies {
+                println!(
+                    "  game {}: {} cubes ({})",
+                    a.game,
+                    a.cubes,
+                    format_cubes(&a.min_bag)
+                );
+            }
+        }
+    }
 }
 
-type GameId = u32;
+#[cfg(test)]
+mod tests {
+    use crate::{
+        min_power, parse_bag, parse_games, possible_games, smallest_bag, Cubes, ImpossibleDraw,
+        Stats,
+    };
 
-/// Min power of the set for this game
-fn min_power(draws: &[[u32; 3]]) -> u32 {
-    draws
-        .iter()
-        .fold([0, 0, 0], |[r1, g1, b1], [r2, g2, b2]| {
-            [r1.max(*r2), g1.max(*g2), b1.max(*b2)]
-        })
-        .iter()
-        .product()
-}
-
-/// Returns an iterator on the IDs of the games that are possible, given the min (r,g,b) count of
-/// cubes in the bag
-fn possible_games(
-    games: &[(u32, Vec<[u32; 3]>)],
-    r: u32,
-    g: u32,
-    b: u32,
-) -> impl Iterator<Item = GameId> + '_ {
-    games.iter().filter_map(move |(id, draws)| {
-        if draws.iter().all(|d| d[0] <= r && d[1] <= g && d[2] <= b) {
-            Some(*id)
-        } else {
-            None
-        }
-    })
+    const EXAMPLE: &str = "\
+Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
+    #[test]
+    fn example() {
+        let games = parse_games(EXAMPLE);
+        let bag = Cubes::from([("red", 12), ("green", 13), ("blue", 14)]);
+        assert_eq!(possible_games(&games, &bag).collect::<Vec<_>>(), [1, 2, 5]);
+        let power: u32 = games.iter().map(|g| min_power(&g.draws)).sum();
+        assert_eq!(power, 2286);
+    }
+
+    #[test]
+    fn bag_queries() {
+        let games = parse_games(EXAMPLE);
+        let bag = parse_bag("12 red, 13 green, 14 blue").unwrap();
+        assert_eq!(bag, Cubes::from([("red", 12), ("green", 13), ("blue", 14)]));
+        assert!(parse_bag("12 red; 13 green").is_err());
+
+        let bag = smallest_bag(&games, &[1, 2, 5]).unwrap();
+        assert_eq!(bag, Cubes::from([("red", 6), ("green", 3), ("blue", 6)]));
+        assert_eq!(possible_games(&games, &bag).collect::<Vec<_>>(), [1, 2, 5]);
+        assert!(smallest_bag(&games, &[6]).is_err());
+    }
+
+    #[test]
+    fn any_color() {
+        let games = parse_games("Game 1: 2 mauve, 1 red\nGame 7: 1 red; 3 dark_olive\n");
+        assert_eq!(games[1].draws[1], Cubes::from([("dark_olive", 3)]));
+        assert_eq!(min_power(&games[0].draws), 2);
+
+        // no olive cubes in the bag
+        let bag = Cubes::from([("red", 1), ("mauve", 2)]);
+        assert_eq!(possible_games(&games, &bag).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn stats() {
+        let games = parse_games(EXAMPLE);
+        let bag = Cubes::from([("red", 12), ("green", 13), ("blue", 14)]);
+        let stats = Stats::new(&games, &[bag], Some(30));
+
+        let red = &stats.colors["red"];
+        assert_eq!((red.draws, red.total, red.min, red.max), (11, 61, 1, 20));
+        assert_eq!(red.histogram[&1], 4);
+
+        assert_eq!(
+            stats.impossible[0].draws,
+            [
+                ImpossibleDraw {
+                    game: 3,
+                    draw: 0,
+                    missing: Cubes::from([("red", 8)])
+                },
+                ImpossibleDraw {
+                    game: 4,
+                    draw: 2,
+                    missing: Cubes::from([("red", 2), ("blue", 1)])
+                },
+            ]
+        );
+
+        // games 3 and 4 need 39 and 32 cubes
+        let anomalies: Vec<_> = stats.anomalies.iter().map(|a| (a.game, a.cubes)).collect();
+        assert_eq!(anomalies, [(3, 39), (4, 32)]);
+    }
 }
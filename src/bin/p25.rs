@@ -0,0 +1,33 @@
+use std::fs;
+
+use advent_of_code_2023::graph::Graph;
+
+fn main() {
+    let content = fs::read_to_string("inputs/p25/wiring.txt").expect("reading input file");
+
+    // Every line is `node: other1 other2 ...`, connections go both ways
+    let mut graph = Graph::new();
+    for line in content.lines().filter(|l| !l.is_empty()) {
+        let (component, others) = line
+            .split_once(':')
+            .expect("line must be in the form `a: b c d`");
+        for other in others.split_whitespace() {
+            graph.add_edge(component.trim(), other);
+        }
+    }
+
+    // We need to disconnect three wires to split the graph in two groups. That is exactly the
+    // minimum cut of the graph (the puzzle guarantees there is only one of weight 3).
+    let cut = graph
+        .min_cut()
+        .expect("graph needs at least two components");
+    assert_eq!(cut.weight, 3, "expected a minimum cut of three wires");
+
+    let group_1 = cut.side.len();
+    let group_2 = graph.len() - group_1;
+    println!(
+        "Cutting {} wires splits into groups of {group_1} and {group_2}",
+        cut.weight
+    );
+    println!("[PART 1] Product of group sizes: {}", group_1 * group_2);
+}
@@ -1,110 +1,407 @@
-use std::fs;
-
-fn main() {
-    let contents: Vec<Vec<u8>> = fs::read("inputs/p3/schematic.txt")
-        .expect("reading file")
-        .split(move |&c| c == b'\n')
-        .map(|s| s.to_vec())
-        .collect();
-
-    // idea: find symbols. Check if there is a number adjacent ot it
-    // (must touch any of the four sides, corners are also ok).
-    let mut part_ids = vec![];
-    let mut sum_of_ratios = 0;
-
-    for (r_idx, row) in contents.iter().enumerate() {
-        for (c_idx, &c) in row.iter().enumerate() {
-            let c = c as char;
-            match c {
-                '.' | '0'..='9' => (), // not a symbol
-                _ if c.is_ascii_punctuation() => {
-                    if let Some((p, ids)) = get_part_id(&contents, r_idx, c_idx) {
-                        // if part is a gear, accumulate gear ratio (two part numbers, ratio = a * b)
-                        if p == '*' && ids.len() == 2 {
-                            sum_of_ratios += ids[0].3 * ids[1].3;
-                        }
+use std::{fmt, fs, str::FromStr};
 
-                        // add part id in vector
-                        for id in ids {
-                            part_ids.push(id);
-                        }
+use anyhow::{bail, Context};
+
+/// A number of the schematic, spanning `len` columns from `(row, col)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Number {
+    row: usize,
+    col: usize,
+    len: usize,
+    value: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Symbol {
+    row: usize,
+    col: usize,
+    symbol: char,
+}
+
+/// How the numbers adjacent to a gear are combined into its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregate {
+    Product,
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn apply(self, values: impl Iterator<Item = u64>) -> u64 {
+        match self {
+            Aggregate::Product => values.product(),
+            Aggregate::Sum => values.sum(),
+            Aggregate::Min => values.min().unwrap_or(0),
+            Aggregate::Max => values.max().unwrap_or(0),
+        }
+    }
+}
+
+/// Which symbols are gears, and how their value is computed. Part 2 is `*:2:product`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GearQuery {
+    /// Gear symbols, any symbol if empty
+    symbols: Vec<char>,
+    /// Exact number of adjacent numbers
+    arity: usize,
+    aggregate: Aggregate,
+}
+
+impl Default for GearQuery {
+    fn default() -> Self {
+        Self {
+            symbols: vec!['*'],
+            arity: 2,
+            aggregate: Aggregate::Product,
+        }
+    }
+}
+
+impl FromStr for GearQuery {
+    type Err = anyhow::Error;
+
+    /// Parses `<symbols>:<arity>:<aggregate>`, e.g. `#$:3:sum`. `<symbols>` can be empty for any
+    /// symbol.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || format!("expected `<symbols>:<arity>:<product|sum|min|max>`, got {s:?}");
+        // the symbols may contain `:` themselves
+        let mut fields = s.rsplitn(3, ':');
+        let (Some(aggregate), Some(arity), Some(symbols)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            bail!(usage());
+        };
+        let aggregate = match aggregate {
+            "product" => Aggregate::Product,
+            "sum" => Aggregate::Sum,
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            _ => bail!(usage()),
+        };
+        Ok(Self {
+            symbols: symbols.chars().collect(),
+            arity: arity.parse().with_context(usage)?,
+            aggregate,
+        })
+    }
+}
+
+impl fmt::Display for GearQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbols: String = self.symbols.iter().collect();
+        write!(f, "{symbols}:{}:{:?}", self.arity, self.aggregate)
+    }
+}
+
+/// Numbers and symbols of the engine schematic, with the bipartite graph of which numbers touch
+/// which symbols (on any of the four sides, corners are also ok).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    /// Indices of the symbols adjacent to each number
+    number_symbols: Vec<Vec<usize>>,
+    /// Indices of the numbers adjacent to each symbol
+    symbol_numbers: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    /// Reads the schematic. Lines may end with `\n` or `\r\n` and have different lengths; any
+    /// character other than a digit or `.` is a symbol. Fails on numbers that do not fit in a
+    /// `u32`.
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let contents: Vec<Vec<char>> = text
+            .lines()
+            .map(|l| l.trim_end_matches('\r').chars().collect())
+            .collect();
+
+        let mut numbers = vec![];
+        let mut symbols = vec![];
+        // index of the number covering each cell
+        let mut cells: Vec<Vec<Option<usize>>> =
+            contents.iter().map(|row| vec![None; row.len()]).collect();
+
+        for (r_idx, row) in contents.iter().enumerate() {
+            let mut c_idx = 0;
+            while c_idx < row.len() {
+                match row[c_idx] {
+                    '0'..='9' => {
+                        let len = row[c_idx..]
+                            .iter()
+                            .position(|c| !c.is_ascii_digit())
+                            .unwrap_or(row.len() - c_idx);
+                        let number_str: String = row[c_idx..c_idx + len].iter().collect();
+                        cells[r_idx][c_idx..c_idx + len].fill(Some(numbers.len()));
+                        numbers.push(Number {
+                            row: r_idx,
+                            col: c_idx,
+                            len,
+                            value: number_str.parse().with_context(|| {
+                                format!("line {}: {number_str} is too large", r_idx + 1)
+                            })?,
+                        });
+                        c_idx += len;
+                        continue;
                     }
+                    '.' => (), // not a symbol
+                    c => symbols.push(Symbol {
+                        row: r_idx,
+                        col: c_idx,
+                        symbol: c,
+                    }),
                 }
-                o => unreachable!("Got impossible char: {o:?}"),
-            };
+                c_idx += 1;
+            }
         }
+
+        let mut number_symbols = vec![vec![]; numbers.len()];
+        let symbol_numbers: Vec<Vec<usize>> = symbols
+            .iter()
+            .enumerate()
+            .map(|(s_idx, s)| {
+                let mut adjacent = vec![];
+                for r in s.row.saturating_sub(1)..=s.row + 1 {
+                    for c in s.col.saturating_sub(1)..=s.col + 1 {
+                        let number = cells.get(r).and_then(|row| row.get(c)).copied().flatten();
+                        if let Some(n) = number.filter(|n| !adjacent.contains(n)) {
+                            adjacent.push(n);
+                            number_symbols[n].push(s_idx);
+                        }
+                    }
+                }
+                adjacent
+            })
+            .collect();
+
+        Ok(Self {
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
+        })
     }
 
-    part_ids.sort();
-    part_ids.dedup();
+    /// Numbers adjacent to at least one symbol
+    fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter_map(|(n, s)| (!s.is_empty()).then_some(n))
+    }
+
+    /// Numbers adjacent to no symbol
+    fn lone_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter_map(|(n, s)| s.is_empty().then_some(n))
+    }
+
+    /// Symbols adjacent to exactly `arity` numbers, with those numbers
+    fn symbols_with_arity(&self, arity: usize) -> impl Iterator<Item = (&Symbol, Vec<&Number>)> {
+        self.symbols
+            .iter()
+            .zip(&self.symbol_numbers)
+            .filter(move |(_, n)| n.len() == arity)
+            .map(|(s, n)| (s, n.iter().map(|&i| &self.numbers[i]).collect()))
+    }
+
+    /// Gears matching the query, with their value
+    fn gears<'s>(&'s self, query: &'s GearQuery) -> impl Iterator<Item = (&'s Symbol, u64)> {
+        self.symbols_with_arity(query.arity)
+            .filter(|(s, _)| query.symbols.is_empty() || query.symbols.contains(&s.symbol))
+            .map(|(s, n)| (s, query.aggregate.apply(n.iter().map(|n| n.value as u64))))
+    }
+
+    /// Sum of the ratios of the gears, `*` symbols adjacent to exactly two numbers
+    fn gear_ratios(&self) -> u64 {
+        self.gears(&GearQuery::default()).map(|(_, v)| v).sum()
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let contents = fs::read_to_string("inputs/p3/schematic.txt").expect("reading file");
+    let schematic = Schematic::parse(&contents)?;
 
     println!(
         "Sum of part ids: {}",
-        part_ids.iter().map(|(_, _, _, n)| n).sum::<u32>()
+        schematic.part_numbers().map(|n| n.value).sum::<u32>()
     );
-    println!("Sum of gear ratios: {sum_of_ratios}");
+    println!("Sum of gear ratios: {}", schematic.gear_ratios());
+
+    // Extra queries
+    for arg in std::env::args().skip(1) {
+        if arg == "--lone" {
+            for n in schematic.lone_numbers() {
+                println!(
+                    "{} at row {} col {} touches no symbol",
+                    n.value, n.row, n.col
+                );
+            }
+        } else if let Some(arity) = arg.strip_prefix("--arity=") {
+            let arity = arity.parse().context("expected `--arity=<numbers>`")?;
+            for (s, numbers) in schematic.symbols_with_arity(arity) {
+                let values: Vec<_> = numbers.iter().map(|n| n.value).collect();
+                println!(
+                    "{} at row {} col {} touches {values:?}",
+                    s.symbol, s.row, s.col
+                );
+            }
+        } else if let Some(query) = arg.strip_prefix("--gears=") {
+            let query: GearQuery = query.parse()?;
+            let total: u64 = schematic.gears(&query).map(|(_, v)| v).sum();
+            println!("Sum of gear values for {query}: {total}");
+        }
+    }
+
+    Ok(())
 }
 
-/// This can create duplicates. Be sure to remove them!
-/// Returns the `(row, start_col, len, id)` of the part.
-/// This is needed to remove duplicates safely, as I'm not sure whether part numbers can be
-/// duplicated.
-fn get_part_id(
-    contents: &Vec<Vec<u8>>,
-    r_idx: usize,
-    c_idx: usize,
-) -> Option<(char, Vec<(usize, usize, usize, u32)>)> {
-    let val =
-        |r_idx: usize, c_idx: usize| contents.get(r_idx).and_then(|row| row.get(c_idx)).copied();
-
-    let digit_pos = [
-        //edges
-        (r_idx - 1, c_idx),
-        (r_idx + 1, c_idx),
-        (r_idx, c_idx - 1),
-        (r_idx, c_idx + 1),
-        //corners
-        (r_idx - 1, c_idx - 1),
-        (r_idx - 1, c_idx + 1),
-        (r_idx + 1, c_idx - 1),
-        (r_idx + 1, c_idx + 1),
-    ]
-    .into_iter()
-    .filter(|p| {
-        val(p.0, p.1)
-            .map(move |c| (c as char).is_ascii_digit())
-            .unwrap_or(false)
-    });
-
-    let mut part_ids: Vec<_> = digit_pos
-        .map(|(dr, mut dc)| {
-            // go left until the start of the number
-            while dc > 0 {
-                // cannot be None, otherwise puzzle makes no sense
-                if (val(dr, dc - 1).unwrap() as char).is_ascii_digit() {
-                    dc -= 1;
-                } else {
-                    break;
-                }
+#[cfg(test)]
+mod tests {
+    use crate::{Aggregate, GearQuery, Number, Schematic, Symbol};
+
+    const EXAMPLE: &str = "\
+467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+
+    #[test]
+    fn example() {
+        let schematic = Schematic::parse(EXAMPLE).unwrap();
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(schematic.symbols.len(), 6);
+        assert_eq!(schematic.part_numbers().map(|n| n.value).sum::<u32>(), 4361);
+        assert_eq!(schematic.gear_ratios(), 467835);
+    }
+
+    #[test]
+    fn queries() {
+        let schematic = Schematic::parse(EXAMPLE).unwrap();
+        let lone: Vec<_> = schematic.lone_numbers().map(|n| n.value).collect();
+        assert_eq!(lone, [114, 58]);
+
+        let pairs: Vec<_> = schematic
+            .symbols_with_arity(2)
+            .map(|(s, n)| (s.symbol, n.iter().map(|n| n.value).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(pairs, [('*', vec![467, 35]), ('*', vec![755, 598])]);
+        assert_eq!(schematic.symbols_with_arity(1).count(), 4);
+        assert_eq!(schematic.symbols_with_arity(3).count(), 0);
+    }
+
+    #[test]
+    fn same_number_twice() {
+        // the two 12 are different parts, and each one touches the symbol once
+        let schematic = Schematic::parse("12.\n.*.\n.12").unwrap();
+        assert_eq!(schematic.symbol_numbers[0].len(), 2);
+        assert_eq!(schematic.gear_ratios(), 144);
+    }
+
+    #[test]
+    fn gear_queries() {
+        let schematic = Schematic::parse(EXAMPLE).unwrap();
+        assert_eq!(sum_of(&schematic, "*:2:product"), 467835);
+        assert_eq!(sum_of(&schematic, "*:2:sum"), 467 + 35 + 755 + 598);
+        assert_eq!(sum_of(&schematic, "*:2:max"), 467 + 755);
+        // `#`, `+`, `$` and one `*` touch a single number
+        assert_eq!(sum_of(&schematic, ":1:min"), 633 + 592 + 664 + 617);
+        assert_eq!(sum_of(&schematic, "#+:1:sum"), 633 + 592);
+        assert_eq!(sum_of(&schematic, "*:3:product"), 0);
+
+        let three = Schematic::parse("1.2\n.#.\n3..\n").unwrap();
+        assert_eq!(sum_of(&three, "#:3:product"), 6);
+
+        assert_eq!(
+            ":::2:min".parse::<GearQuery>().unwrap(),
+            GearQuery {
+                symbols: vec![':', ':'],
+                arity: 2,
+                aggregate: Aggregate::Min
             }
+        );
+        assert!("*:2".parse::<GearQuery>().is_err());
+        assert!("*:x:sum".parse::<GearQuery>().is_err());
+        assert!("*:2:avg".parse::<GearQuery>().is_err());
+    }
 
-            let len = contents[dr][dc..]
-                .iter()
-                .position(move |&c| !(c as char).is_ascii_digit())
-                .unwrap_or(contents[dr].len() - dc);
-            let number_str = std::str::from_utf8(&contents[dr][dc..(dc + len)]).unwrap();
-            let number = u32::from_str_radix(number_str, 10).unwrap();
+    fn sum_of(schematic: &Schematic, query: &str) -> u64 {
+        schematic
+            .gears(&query.parse().unwrap())
+            .map(|(_, v)| v)
+            .sum()
+    }
 
-            (dr, dc, len, number)
-        })
-        .collect();
+    #[test]
+    fn line_endings() {
+        let lf = Schematic::parse(EXAMPLE).unwrap();
+        let crlf = EXAMPLE.replace('\n', "\r\n");
+        assert_eq!(Schematic::parse(&crlf).unwrap(), lf);
+        assert_eq!(Schematic::parse(&(crlf + "\r\n")).unwrap(), lf);
+        assert_eq!(Schematic::parse(&format!("{EXAMPLE}\n")).unwrap(), lf);
+        // no newline after a last CR
+        assert_eq!(
+            Schematic::parse("1*\r").unwrap(),
+            Schematic::parse("1*").unwrap()
+        );
+    }
+
+    #[test]
+    fn ragged_rows() {
+        // the symbol of the short row still touches the numbers of the longer ones
+        let schematic = Schematic::parse("..12\n*\n3....5\n.....#").unwrap();
+        let values: Vec<_> = schematic.part_numbers().map(|n| n.value).collect();
+        assert_eq!(values, [3, 5]);
+        // a cell beyond the end of a row touches nothing
+        let schematic = Schematic::parse("1\n..*").unwrap();
+        assert_eq!(schematic.part_numbers().count(), 0);
+    }
 
-    part_ids.sort();
-    part_ids.dedup();
+    #[test]
+    fn borders() {
+        // symbols in every corner and on every edge
+        let schematic = Schematic::parse("*1.2#\n3...4\n+.5.-\n6...7\n%8.9&").unwrap();
+        assert_eq!(schematic.lone_numbers().count(), 1);
+        assert_eq!(schematic.symbols.len(), 6);
+        assert_eq!(schematic.part_numbers().count(), 8);
 
-    if part_ids.is_empty() {
-        None
-    } else {
-        Some((contents[r_idx][c_idx] as char, part_ids))
+        let schematic = Schematic::parse("1\n*").unwrap();
+        assert_eq!(
+            schematic.numbers,
+            [Number {
+                row: 0,
+                col: 0,
+                len: 1,
+                value: 1
+            }]
+        );
+        assert_eq!(schematic.symbol_numbers, [vec![0]]);
+
+        assert!(Schematic::parse("4294967295*").is_ok());
+        assert!(Schematic::parse("4294967296*").is_err());
+    }
+
+    #[test]
+    fn any_symbol() {
+        let schematic = Schematic::parse("1x.\n..§2\n3 4").unwrap();
+        let symbols: Vec<_> = schematic.symbols.iter().map(|s| s.symbol).collect();
+        assert_eq!(symbols, ['x', '§', ' ']);
+        assert_eq!(
+            schematic.symbols[1],
+            Symbol {
+                row: 1,
+                col: 2,
+                symbol: '§'
+            }
+        );
+        assert_eq!(schematic.lone_numbers().count(), 0);
     }
 }
@@ -1,47 +1,452 @@
-use std::fs;
+use std::{collections::HashSet, fs, str::FromStr};
 
-fn main() {
-    let content = fs::read_to_string("inputs/p4/scratchcards.txt").expect("reading problem input");
-    let card_matches: Vec<_> = content
+use anyhow::{bail, Context};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Scratchcard {
+    id: usize,
+    winning: Vec<u32>,
+    held: Vec<u32>,
+}
+
+impl Scratchcard {
+    /// Parses `Card N: <winning numbers> | <held numbers>`
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        let (card, numbers) = line.split_once(':').context("missing `Card N:` prefix")?;
+        let id = card
+            .strip_prefix("Card")
+            .filter(|id| id.starts_with(char::is_whitespace))
+            .and_then(|id| id.trim().parse().ok())
+            .with_context(|| format!("expected `Card N`, got {card:?}"))?;
+        let (winning, held) = numbers.split_once('|').context("missing `|` separator")?;
+        let parse = |nums: &str| -> anyhow::Result<Vec<u32>> {
+            nums.split_whitespace()
+                .map(|n| n.parse().with_context(|| format!("parsing number {n:?}")))
+                .collect()
+        };
+        Ok(Self {
+            id,
+            winning: parse(winning)?,
+            held: parse(held)?,
+        })
+    }
+
+    /// Number of distinct held numbers that are winning
+    fn matches(&self) -> usize {
+        let winning: HashSet<_> = self.winning.iter().collect();
+        let held: HashSet<_> = self.held.iter().collect();
+        winning.intersection(&held).count()
+    }
+
+    /// Numbers appearing more than once on the same side of the card, as `(side, number)`
+    fn duplicates(&self) -> Vec<(&'static str, u32)> {
+        let mut duplicates = vec![];
+        for (side, numbers) in [("winning", &self.winning), ("held", &self.held)] {
+            let mut seen = HashSet::new();
+            for &n in numbers {
+                if !seen.insert(n) && !duplicates.contains(&(side, n)) {
+                    duplicates.push((side, n));
+                }
+            }
+        }
+        duplicates
+    }
+}
+
+/// Parses the cards, sorted by ID. Numbers duplicated on one side of a card are an error if
+/// `strict`, otherwise they are returned as warnings.
+fn parse_cards(content: &str, strict: bool) -> anyhow::Result<(Vec<Scratchcard>, Vec<String>)> {
+    let mut cards = content
         .lines()
-        .map(|line| {
-            let mut line_nums = line
-                .split(':')
-                .skip(1)
-                .flat_map(|rest| rest.split('|'))
-                .flat_map(|nums| nums.split_whitespace())
-                .map(|n| u32::from_str_radix(n, 10))
-                .collect::<Result<Vec<_>, _>>()
-                .expect("parsing number in file");
-            line_nums.sort();
-            let matches = line_nums
-                .windows(2)
-                .filter(|pair| pair[0] == pair[1])
-                .count();
-            matches
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| Scratchcard::parse(line).with_context(|| format!("line {}", i + 1)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    cards.sort_by_key(|c| c.id);
+    if let Some(pair) = cards.windows(2).find(|pair| pair[0].id == pair[1].id) {
+        bail!("card {} appears twice", pair[0].id);
+    }
+
+    let mut warnings = vec![];
+    for card in &cards {
+        for (side, n) in card.duplicates() {
+            warnings.push(format!(
+                "card {}: {n} appears more than once in the {side} numbers",
+                card.id
+            ));
+        }
+    }
+    if strict && !warnings.is_empty() {
+        bail!(warnings.join("\n"));
+    }
+    Ok((cards, warnings))
+}
+
+/// How the matches of a card turn into points
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Scoring {
+    /// 1 point for the first match, doubled for each other one (part 1)
+    Doubling,
+    /// The given points for each match
+    Linear(u64),
+    /// Points for 0, 1, 2... matches. More matches than the table has get its last entry.
+    Table(Vec<u64>),
+}
+
+impl Scoring {
+    /// Points of a card, saturating at `u64::MAX`
+    fn score(&self, matches: usize) -> u64 {
+        match self {
+            Scoring::Doubling => match matches {
+                0 => 0,
+                m => u32::try_from(m - 1)
+                    .ok()
+                    .and_then(|m| 1u64.checked_shl(m))
+                    .unwrap_or(u64::MAX),
+            },
+            Scoring::Linear(points) => points.saturating_mul(matches as u64),
+            Scoring::Table(table) => table.get(matches).or(table.last()).copied().unwrap_or(0),
+        }
+    }
+}
+
+/// Which cards a card wins copies of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CopyRule {
+    /// No copies (part 1)
+    None,
+    /// One copy of each of the next `m` cards, `m` being the matches, at most `cap`. Copies stop
+    /// at the end of the table (part 2).
+    Next { cap: Option<usize> },
+    /// Like `Next`, but copies continue from the first card after the last one, without ever
+    /// reaching the card itself. Copies won of cards that have already been scratched do not win
+    /// anything more.
+    Wrap { cap: Option<usize> },
+}
+
+/// Scratches cards, scoring them and winning copies according to the rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Engine {
+    scoring: Scoring,
+    copies: CopyRule,
+}
+
+/// Copies of each card ended with (including the original), and total points of all the copies
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Outcome {
+    copies: Vec<u64>,
+    points: u64,
+    /// Cards that won copies of each card, with how many, in order
+    sources: Vec<Vec<(usize, u64)>>,
+}
+
+impl Outcome {
+    fn cards(&self) -> u64 {
+        self.copies.iter().sum()
+    }
+
+    /// Table of the copies of each card, and of the cards they were won from
+    fn explain(&self, cards: &[Scratchcard]) -> String {
+        let mut table = format!("{:>5} {:>10}  won from (card +copies)\n", "card", "copies");
+        for (i, card) in cards.iter().enumerate() {
+            let sources: Vec<_> = self.sources[i]
+                .iter()
+                .map(|&(from, n)| format!("{} +{n}", cards[from].id))
+                .collect();
+            let sources = if sources.is_empty() {
+                "-".to_string()
+            } else {
+                sources.join(", ")
+            };
+            table += &format!("{:>5} {:>10}  {sources}\n", card.id, self.copies[i]);
+        }
+        table
+    }
+
+    /// DOT graph of the cards, with an arc to each card from the cards it was won from
+    fn dot(&self, cards: &[Scratchcard]) -> String {
+        let mut dot = "digraph scratchcards {\n".to_string();
+        for (i, card) in cards.iter().enumerate() {
+            dot += &format!(
+                "    {} [label=\"Card {}\\n{} copies\"];\n",
+                card.id, card.id, self.copies[i]
+            );
+        }
+        for (i, card) in cards.iter().enumerate() {
+            for &(from, n) in &self.sources[i] {
+                dot += &format!("    {} -> {} [label=\"{n}\"];\n", cards[from].id, card.id);
+            }
+        }
+        dot + "}\n"
+    }
+}
+
+impl Engine {
+    fn part1() -> Self {
+        Self {
+            scoring: Scoring::Doubling,
+            copies: CopyRule::None,
+        }
+    }
+
+    fn part2() -> Self {
+        Self {
+            scoring: Scoring::Doubling,
+            copies: CopyRule::Next { cap: None },
+        }
+    }
+
+    /// Cards that the card at `card_idx` with `m` matches wins a copy of
+    fn won(&self, card_idx: usize, m: usize, cards_len: usize) -> impl Iterator<Item = usize> {
+        let (range, wrap) = match self.copies {
+            CopyRule::None => (0..0, false),
+            CopyRule::Next { cap } => {
+                let m = cap.map_or(m, |cap| m.min(cap));
+                ((card_idx + 1)..cards_len.min(card_idx + 1 + m), false)
+            }
+            CopyRule::Wrap { cap } => {
+                let m = cap.map_or(m, |cap| m.min(cap)).min(cards_len - 1);
+                ((card_idx + 1)..(card_idx + 1 + m), true)
+            }
+        };
+        range.map(move |i| if wrap { i % cards_len } else { i })
+    }
+
+    /// Scratches the cards in order, given the matches of each one
+    fn run(&self, card_matches: &[usize]) -> Outcome {
+        // For each card appearing `n` times containing `m` matches, increment the won cards by
+        // `n` each.
+        let cards_len = card_matches.len();
+        let mut card_count = vec![1u64; cards_len];
+        let mut sources = vec![vec![]; cards_len];
+        let mut points = 0;
+
+        for (card_idx, &m) in card_matches.iter().enumerate() {
+            let n = card_count[card_idx];
+            points = n
+                .saturating_mul(self.scoring.score(m))
+                .saturating_add(points);
+            for won in self.won(card_idx, m, cards_len) {
+                card_count[won] += n;
+                sources[won].push((card_idx, n));
+            }
+        }
+
+        Outcome {
+            copies: card_count,
+            points,
+            sources,
+        }
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = anyhow::Error;
+
+    /// `doubling`, `linear:<points>` or `table:<points>,<points>,...`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, arg) = s.split_once(':').unwrap_or((s, ""));
+        Ok(match kind {
+            "doubling" => Scoring::Doubling,
+            "linear" => Scoring::Linear(arg.parse().context("expected `linear:<points>`")?),
+            "table" => Scoring::Table(
+                arg.split(',')
+                    .map(|p| p.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .context("expected `table:<points>,<points>,...`")?,
+            ),
+            _ => bail!(
+                "unknown scoring {s:?}, expected doubling, linear:<points> or table:<points>,..."
+            ),
         })
-        .collect();
+    }
+}
 
-    let score: u32 = card_matches
-        .iter()
-        .map(move |&matches| 2u32.pow(matches as u32) / 2)
-        .sum();
+impl FromStr for CopyRule {
+    type Err = anyhow::Error;
 
-    println!("Score: {score}");
+    /// `none`, `next[:<cap>]` or `wrap[:<cap>]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, cap) = s.split_once(':').unwrap_or((s, ""));
+        let cap = match cap {
+            "" => None,
+            cap => Some(cap.parse().context("expected a cap on the copies")?),
+        };
+        Ok(match kind {
+            "none" => CopyRule::None,
+            "next" => CopyRule::Next { cap },
+            "wrap" => CopyRule::Wrap { cap },
+            _ => bail!("unknown copy rule {s:?}, expected none, next[:<cap>] or wrap[:<cap>]"),
+        })
+    }
+}
+
+/// Engine configured with `--scoring=...` and `--copies=...`, starting from part 2's rules.
+/// Returns `None` if neither is given.
+fn engine_from_args() -> anyhow::Result<Option<Engine>> {
+    let mut engine = None;
+    for arg in std::env::args().skip(1) {
+        if let Some(scoring) = arg.strip_prefix("--scoring=") {
+            engine.get_or_insert_with(Engine::part2).scoring = scoring.parse()?;
+        } else if let Some(copies) = arg.strip_prefix("--copies=") {
+            engine.get_or_insert_with(Engine::part2).copies = copies.parse()?;
+        }
+    }
+    Ok(engine)
+}
 
-    // now handle duplication of cards
-    // For each card appearing `n` times containing `m` matches, increment the subsequent `m` cards by `n` each.
-    let cards_len = content.lines().count();
-    let mut card_count = vec![1u64; cards_len];
+fn main() -> anyhow::Result<()> {
+    let content = fs::read_to_string("inputs/p4/scratchcards.txt").expect("reading problem input");
+    let strict = std::env::args().any(|a| a == "--strict");
+    let (cards, warnings) = parse_cards(&content, strict)?;
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
+    let card_matches: Vec<_> = cards.iter().map(Scratchcard::matches).collect();
 
-    for (card_idx, m) in card_matches.into_iter().enumerate() {
-        let n = card_count[card_idx];
-        if let Some(to_increment) =
-            card_count.get_mut((card_idx + 1)..cards_len.min(card_idx + 1 + m))
-        {
-            to_increment.iter_mut().for_each(|count| *count += n);
+    // Provenance of the copies, with the custom rules if any
+    if let Some(explain) = std::env::args().find(|a| a.starts_with("--explain")) {
+        let outcome = engine_from_args()?
+            .unwrap_or_else(Engine::part2)
+            .run(&card_matches);
+        match explain.as_str() {
+            "--explain" => print!("{}", outcome.explain(&cards)),
+            "--explain=dot" => print!("{}", outcome.dot(&cards)),
+            _ => bail!("expected `--explain[=dot]`, got {explain:?}"),
         }
+        return Ok(());
+    }
+
+    let score = Engine::part1().run(&card_matches).points;
+    println!("Score: {score}");
+
+    let outcome = Engine::part2().run(&card_matches);
+    println!("Total cards: {}", outcome.cards());
+
+    if let Some(engine) = engine_from_args()? {
+        let outcome = engine.run(&card_matches);
+        println!(
+            "With {:?} scoring and {:?} copies: {} points, {} cards",
+            engine.scoring,
+            engine.copies,
+            outcome.points,
+            outcome.cards()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_cards, CopyRule, Engine, Scoring, Scratchcard};
+
+    const EXAMPLE: &str = "\
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    fn matches() -> Vec<usize> {
+        let (cards, warnings) = parse_cards(EXAMPLE, true).unwrap();
+        assert!(warnings.is_empty());
+        cards.iter().map(Scratchcard::matches).collect()
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(matches(), [4, 2, 2, 1, 0, 0]);
+        assert_eq!(Engine::part1().run(&matches()).points, 13);
+        let outcome = Engine::part2().run(&matches());
+        assert_eq!(outcome.copies, [1, 2, 4, 8, 14, 1]);
+        assert_eq!(outcome.cards(), 30);
+    }
+
+    #[test]
+    fn rules() {
+        let run = |scoring: &str, copies: &str| {
+            Engine {
+                scoring: scoring.parse().unwrap(),
+                copies: copies.parse().unwrap(),
+            }
+            .run(&matches())
+        };
+
+        assert_eq!(run("linear:3", "none").points, 3 * 9);
+        assert_eq!(run("table:0,1,5", "none").points, 5 + 5 + 5 + 1);
+        // card 1 wins copies of 2 and 3 only
+        assert_eq!(run("doubling", "next:2").copies, [1, 2, 4, 7, 12, 1]);
+        assert_eq!(run("doubling", "wrap:2"), run("doubling", "next:2"));
+
+        // the last card wins copies of the first two, which have already been scratched
+        let wrap = Engine {
+            scoring: Scoring::Doubling,
+            copies: CopyRule::Wrap { cap: None },
+        };
+        let outcome = wrap.run(&[1, 0, 2]);
+        assert_eq!(outcome.copies, [2, 3, 1]);
+        assert_eq!(outcome.points, 1 + 2);
+        // the first card wins copies of all the others, but not of itself
+        assert_eq!(wrap.run(&[9, 0, 0]).copies, [1, 2, 2]);
+
+        assert_eq!(Scoring::Doubling.score(0), 0);
+        assert_eq!(Scoring::Doubling.score(64), 1 << 63);
+        assert_eq!(Scoring::Doubling.score(65), u64::MAX);
+        assert_eq!(Scoring::Linear(u64::MAX).score(2), u64::MAX);
+
+        assert!("linear".parse::<Scoring>().is_err());
+        assert!("next:x".parse::<CopyRule>().is_err());
+    }
+
+    #[test]
+    fn set_semantics() {
+        let card = Scratchcard::parse("Card 7: 1 2 2 3 | 2 2 3 3 4").unwrap();
+        assert_eq!(card.id, 7);
+        assert_eq!(card.matches(), 2);
+        assert_eq!(
+            card.duplicates(),
+            [("winning", 2), ("held", 2), ("held", 3)]
+        );
+
+        let content = "Card 2: 1 2 | 1 1\nCard  1: 5 | 5\n";
+        let (cards, warnings) = parse_cards(content, false).unwrap();
+        assert_eq!(cards.iter().map(|c| c.id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(
+            warnings,
+            ["card 2: 1 appears more than once in the held numbers"]
+        );
+        assert!(parse_cards(content, true).is_err());
+    }
+
+    #[test]
+    fn card_ids() {
+        assert!(Scratchcard::parse("Card x: 1 | 1").is_err());
+        assert!(Scratchcard::parse("Cards 1: 1 | 1").is_err());
+        assert!(Scratchcard::parse("Card1: 1 | 1").is_err());
+        assert!(Scratchcard::parse("Card 1: 1 1").is_err());
+        assert!(parse_cards("Card 1: 1 | 1\nCard 1: 2 | 2", false).is_err());
     }
 
-    println!("Total cards: {}", card_count.iter().sum::<u64>());
+    #[test]
+    fn provenance() {
+        let (cards, _) = parse_cards(EXAMPLE, true).unwrap();
+        let outcome = Engine::part2().run(&matches());
+        assert_eq!(outcome.sources[0], []);
+        // card 4 has 1 original, 1 copy from card 1, 2 from card 2, and 4 from card 3
+        assert_eq!(outcome.sources[3], [(0, 1), (1, 2), (2, 4)]);
+
+        let table = outcome.explain(&cards);
+        assert_eq!(
+            table.lines().nth(4),
+            Some("    4          8  1 +1, 2 +2, 3 +4")
+        );
+        assert_eq!(table.lines().nth(6), Some("    6          1  -"));
+
+        let dot = outcome.dot(&cards);
+        assert!(dot.starts_with("digraph scratchcards {\n    1 [label=\"Card 1\\n1 copies\"];"));
+        assert!(dot.contains("    4 -> 5 [label=\"8\"];\n"));
+        assert_eq!(dot.matches("->").count(), 4 + 2 + 2 + 1);
+    }
 }
@@ -1,5 +1,17 @@
-use std::{collections::VecDeque, fs};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fs,
+    ops::Range,
+};
 
+use advent_of_code_2023::{
+    explore::{self, Command, Explore},
+    playback::{Playback, Step},
+    render::{Color, Frame, Style},
+};
+
+use anyhow::{bail, Context};
 use pest::Parser;
 use pest_derive::Parser;
 
@@ -15,153 +27,697 @@ file = { SOI ~ seeds ~ (NEWLINE ~ NEWLINE ~ map)* ~ NEWLINE* ~ EOI }
 struct AlmanacParser;
 
 /// Mapping range, mapping `src..(src+len)` to elements starting at `dst`
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 struct Mapping {
-    src: usize,
-    dst: usize,
-    len: usize,
+    src: u64,
+    dst: u64,
+    len: u64,
 }
 
-fn main() {
-    println!("Problem number 5");
-    let content = fs::read_to_string("inputs/p5/almanac.txt").expect("reading input file");
-    let file = AlmanacParser::parse(Rule::file, &content)
-        .expect("parsing input almanac")
-        .next()
-        .unwrap();
+impl Mapping {
+    /// Where `num` (in the source range) goes
+    fn translate(&self, num: u64) -> u64 {
+        num - self.src + self.dst
+    }
+}
 
-    // the maps are pretty huge, thus representing them in a dumb way (with index-to-value array
-    // maps) is not very smart (lots of mem used).
-    // I use my own type instead
+/// Piecewise-linear map over `0..u64::MAX`: every piece translates a range of values to another
+/// one. Pieces are sorted and cover the whole domain, values not covered by an almanac mapping
+/// being in identity pieces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RangeMap {
+    pieces: Vec<Mapping>,
+}
+
+impl RangeMap {
+    fn identity() -> Self {
+        Self {
+            pieces: vec![Mapping {
+                src: 0,
+                dst: 0,
+                len: u64::MAX,
+            }],
+        }
+    }
+
+    /// Map of an almanac table. Mappings with overlapping sources are an error, as a value
+    /// would have two images.
+    fn from_mappings(mappings: &[Mapping]) -> anyhow::Result<Self> {
+        if let Some(m) = mappings
+            .iter()
+            .find(|m| m.src.checked_add(m.len).is_none() || m.dst.checked_add(m.len).is_none())
+        {
+            bail!("mapping {m:?} goes past {}", u64::MAX);
+        }
+        let mut sorted: Vec<_> = mappings.iter().filter(|m| m.len > 0).copied().collect();
+        sorted.sort_by_key(|m| m.src);
+        if let Some(w) = sorted.windows(2).find(|w| w[0].src + w[0].len > w[1].src) {
+            bail!("overlapping source ranges in {:?} and {:?}", w[0], w[1]);
+        }
+        let mut pieces = vec![];
+        let mut start = 0;
+        for m in sorted {
+            if start < m.src {
+                pieces.push(Mapping {
+                    src: start,
+                    dst: start,
+                    len: m.src - start,
+                });
+            }
+            start = m.src + m.len;
+            pieces.push(m);
+        }
+        if start < u64::MAX {
+            pieces.push(Mapping {
+                src: start,
+                dst: start,
+                len: u64::MAX - start,
+            });
+        }
+        Ok(Self::merged(pieces))
+    }
 
-    // invariant: for all i,j: maps[i].len() == maps[j].len()
-    let mut maps: Vec<Vec<_>> = vec![];
+    /// Joins consecutive pieces that translate by the same amount
+    fn merged(pieces: Vec<Mapping>) -> Self {
+        let mut merged: Vec<Mapping> = vec![];
+        for p in pieces {
+            match merged.last_mut() {
+                Some(last) if last.src + last.len == p.src && last.dst + last.len == p.dst => {
+                    last.len += p.len
+                }
+                _ => merged.push(p),
+            }
+        }
+        Self { pieces: merged }
+    }
 
-    let mut seeds_and_maps = file.into_inner();
+    /// Index of the piece containing `num`
+    fn piece(&self, num: u64) -> usize {
+        self.pieces.partition_point(|p| p.src + p.len <= num)
+    }
 
-    let seeds: Vec<_> = seeds_and_maps
-        .next()
-        .expect("getting seeds list")
-        .into_inner()
-        .map(|seed_num| usize::from_str_radix(seed_num.as_str(), 10).unwrap())
-        .collect();
+    /// Image of `num`, or `None` past the domain, which ends before `u64::MAX`
+    fn checked_apply(&self, num: u64) -> Option<u64> {
+        self.pieces.get(self.piece(num)).map(|p| p.translate(num))
+    }
 
-    println!("Registered seeds: {seeds:?}");
+    #[cfg(test)]
+    fn apply(&self, num: u64) -> u64 {
+        self.checked_apply(num).expect("value past the map domain")
+    }
 
-    // maps seem to be in-order (previous maps' dest is the next maps' src)
-    // thus storing them in ordered vector
-    for map in seeds_and_maps {
-        match map.as_rule() {
-            Rule::map => {
-                let mut map_children = map.into_inner();
-                let from = map_children.next().expect("getting map source").as_str();
-                let to = map_children
-                    .next()
-                    .expect("getting map destination")
-                    .as_str();
-
-                println!("{from}->{to} map:");
-                let mut ranges = vec![];
-                for range in map_children {
-                    let [dst, src, len] = range
-                        .into_inner()
-                        .map(|n| usize::from_str_radix(n.as_str(), 10).unwrap())
-                        .collect::<Vec<_>>()
-                        .try_into()
-                        .expect("wrong range length");
-
-                    println!("\t{src}->{dst}\t(len {len})");
-
-                    ranges.push(Mapping { src, dst, len })
+    /// Maps every range. Ranges covering several pieces are split.
+    fn apply_ranges(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut result = vec![];
+        for range in ranges.iter().filter(|r| !r.is_empty()) {
+            for p in &self.pieces[self.piece(range.start)..] {
+                if p.src >= range.end {
+                    break;
                 }
-                maps.push(ranges);
+                let start = range.start.max(p.src);
+                let end = range.end.min(p.src + p.len);
+                result.push(p.translate(start)..p.translate(end - 1) + 1);
             }
-            Rule::EOI => (),
-            _ => unreachable!(),
         }
+        result
     }
 
-    // part 1: lowest location number corresponding to any initial seed number.
-    // Idea: obtain by mapping each seed to the next table, until the last.
-    let mut tt = seeds.clone();
+    /// Ranges of the values mapped into `range`, sorted
+    fn preimage(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        let mut result: Vec<Range<u64>> = vec![];
+        for p in &self.pieces {
+            let start = range.start.max(p.dst);
+            let end = range.end.min(p.dst + p.len);
+            if start < end {
+                result.push((p.src + (start - p.dst))..(p.src + (end - p.dst)));
+            }
+        }
+        result.sort_by_key(|r| r.start);
+        merge_ranges(result)
+    }
+
+    /// The inverse map, if this one is a bijection (the images of the pieces do not overlap)
+    fn invert(&self) -> Option<RangeMap> {
+        let mut pieces: Vec<_> = self
+            .pieces
+            .iter()
+            .map(|p| Mapping {
+                src: p.dst,
+                dst: p.src,
+                len: p.len,
+            })
+            .collect();
+        pieces.sort_by_key(|p| p.src);
+        let mut start = 0;
+        for p in &pieces {
+            if p.src != start {
+                return None;
+            }
+            start += p.len;
+        }
+        Some(Self::merged(pieces))
+    }
 
-    for map in &maps {
-        for num in tt.iter_mut() {
-            *num = map
+    /// Lowest value whose image is in one of `ranges`. On an inverted map, this searches
+    /// upward for the first value coming from `ranges`.
+    fn first_mapped_into(&self, ranges: &[Range<u64>]) -> Option<u64> {
+        // pieces are sorted, the first one hitting a range has the lowest value
+        self.pieces.iter().find_map(|p| {
+            ranges
                 .iter()
-                .find(|mapping| (mapping.src..(mapping.src + mapping.len)).contains(num))
-                .map(|mapping| *num - mapping.src + mapping.dst)
-                .unwrap_or(*num);
+                .filter_map(|r| {
+                    let start = r.start.max(p.dst);
+                    (start < r.end.min(p.dst + p.len)).then(|| p.src + (start - p.dst))
+                })
+                .min()
+        })
+    }
+
+    /// Map applying `self`, then `then`
+    fn compose(&self, then: &RangeMap) -> RangeMap {
+        let mut pieces = vec![];
+        for p in &self.pieces {
+            // split the image of the piece along the pieces of `then`
+            let image = p.dst..(p.dst + p.len);
+            for q in &then.pieces[then.piece(image.start)..] {
+                if q.src >= image.end {
+                    break;
+                }
+                let start = image.start.max(q.src);
+                let end = image.end.min(q.src + q.len);
+                pieces.push(Mapping {
+                    src: p.src + (start - p.dst),
+                    dst: q.translate(start),
+                    len: end - start,
+                });
+            }
+        }
+        Self::merged(pieces)
+    }
+}
+
+/// Seeds and maps of the almanac. The maps form a graph of categories, every category being
+/// the source of at most one map and the target of at most one, without cycles: maps may come
+/// in any order and be chained between any two categories of a path.
+struct Almanac<'s> {
+    seeds: Vec<u64>,
+    /// Target category and map, by source category
+    maps: BTreeMap<&'s str, (&'s str, RangeMap)>,
+}
+
+impl<'s> Almanac<'s> {
+    fn parse(content: &'s str) -> anyhow::Result<Self> {
+        let mut seeds_and_maps = AlmanacParser::parse(Rule::file, content)
+            .context("parsing input almanac")?
+            .next()
+            .unwrap()
+            .into_inner();
+
+        let seeds = seeds_and_maps
+            .next()
+            .expect("getting seeds list")
+            .into_inner()
+            .map(|seed_num| seed_num.as_str().parse::<u64>())
+            .collect::<Result<_, _>>()?;
+
+        let mut maps = BTreeMap::new();
+        let mut targets = BTreeSet::new();
+        for map in seeds_and_maps.filter(|p| p.as_rule() == Rule::map) {
+            let mut map_children = map.into_inner();
+            let from = map_children.next().expect("getting map source").as_str();
+            let to = map_children
+                .next()
+                .expect("getting map destination")
+                .as_str();
+
+            let mut ranges = vec![];
+            for range in map_children {
+                let [dst, src, len] = range
+                    .into_inner()
+                    .map(|n| n.as_str().parse())
+                    .collect::<Result<Vec<_>, _>>()?
+                    .try_into()
+                    .expect("wrong range length");
+                ranges.push(Mapping { src, dst, len })
+            }
+            let range_map =
+                RangeMap::from_mappings(&ranges).with_context(|| format!("{from}-to-{to} map"))?;
+
+            if !targets.insert(to) {
+                bail!("several maps to {to}");
+            }
+            if maps.insert(from, (to, range_map)).is_some() {
+                bail!("several maps from {from}");
+            }
+        }
+
+        // every walk has at most one step per map, unless it goes around a cycle
+        for &start in maps.keys() {
+            let mut category = start;
+            for _ in 0..=maps.len() {
+                match maps.get(category) {
+                    Some((to, _)) if *to == start => bail!("maps from {start} lead back to it"),
+                    Some((to, _)) => category = to,
+                    None => break,
+                }
+            }
+        }
+
+        Ok(Self { seeds, maps })
+    }
+
+    /// Categories from `from` to `to`, both included
+    fn path<'a>(&'a self, from: &'a str, to: &str) -> anyhow::Result<Vec<&'a str>> {
+        let mut path = vec![from];
+        let mut category = from;
+        while category != to {
+            match self.maps.get(category) {
+                Some((next, _)) => category = next,
+                None => bail!("no maps lead from {from} to {to}"),
+            }
+            path.push(category);
+        }
+        Ok(path)
+    }
+
+    /// Maps between consecutive categories of `path`
+    fn maps_along(&self, path: &[&str]) -> Vec<RangeMap> {
+        path.windows(2).map(|w| self.maps[w[0]].1.clone()).collect()
+    }
+
+    /// Single map from `from` to `to`
+    fn chain(&self, from: &str, to: &str) -> anyhow::Result<RangeMap> {
+        let path = self.path(from, to)?;
+        Ok(self
+            .maps_along(&path)
+            .iter()
+            .fold(RangeMap::identity(), |chain, map| chain.compose(map)))
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Problem number 5");
+    let content = fs::read_to_string("inputs/p5/almanac.txt").expect("reading input file");
+
+    // the maps are pretty huge, thus representing them in a dumb way (with index-to-value array
+    // maps) is not very smart (lots of mem used).
+    // I use my own type instead
+    let almanac = Almanac::parse(&content)?;
+    let seeds = &almanac.seeds;
+    println!("Registered seeds: {seeds:?}");
+
+    // category of the values after each map, starting from the seeds
+    let categories = almanac.path("seed", "location")?;
+    let maps = almanac.maps_along(&categories);
+    for (map, from_to) in maps.iter().zip(categories.windows(2)) {
+        println!("{}->{} map:", from_to[0], from_to[1]);
+        for p in map.pieces.iter().filter(|p| p.src != p.dst) {
+            println!("\t{}->{}\t(len {})", p.src, p.dst, p.len);
         }
     }
 
-    println!(
-        "Lowest location number corresponding to a valid seed: {}",
-        tt.iter().min().unwrap()
-    );
+    // the whole seed->location chain, as a single map
+    let chain = almanac.chain("seed", "location")?;
+
+    // part 1: lowest location number corresponding to any initial seed number.
+    let locations = seeds
+        .iter()
+        .map(|&seed| {
+            chain
+                .checked_apply(seed)
+                .with_context(|| format!("seed {seed} is past the maps"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let min_location = locations.into_iter().min().unwrap();
+    println!("Lowest location number corresponding to a valid seed: {min_location}");
 
     // part 2. Need to change the meaning of `seeds`. Now it contains pairs of (start, len).
     // I basically do the same thing, but I can't do that for each seed number.
-    // Instead, I transform **ranges** by mapping them to the next table thingy.
-    //
-
-    let mut ranges: Vec<_> = seeds
+    // Instead, I transform **ranges** through the chain.
+    let ranges = seeds
         .chunks_exact(2)
-        .map(|pair| pair[0]..(pair[0] + pair[1]))
-        .collect();
+        .map(|pair| {
+            let end = pair[0]
+                .checked_add(pair[1])
+                .with_context(|| format!("seed range {} {} is past the maps", pair[0], pair[1]))?;
+            Ok(pair[0]..end)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
-    for map in &maps {
-        let mut result = vec![];
-        for src_range in ranges.iter_mut() {
-            let mut leftover = VecDeque::from_iter(Some(src_range.clone()));
-            while let Some(num_range) = leftover.pop_front() {
-                let result_len = result.len();
-                for mapping in map {
-                    // Now map the num range to the next table. Note that we may have to split it in UP TO
-                    // 3 COMPONENTS for every mapping! Specifically, the situations might be
-                    // 1. [     ]    (    )                         not intersecting at all
-                    // 2. (    [     ]) or (       [     )    ]     intersecting, only 2 seed ranges are
-                    //    produces
-                    // 3. (  [  ]   ) intersecting, 3 seed ranges are produced
-                    // Intersect at all?
-                    if !(num_range.start < (mapping.src + mapping.len)
-                        && num_range.end > mapping.src)
-                    {
-                        continue; // no intesection
-                    }
+    if let Some(playback) = Playback::from_args("p5")? {
+        let mut splitting = RangeSplitting::new(&categories, &maps, ranges.clone());
+        playback.run(&mut splitting, "ranges")?;
+    }
 
-                    // try to split in 3 parts
-                    // prefix of num range with no intersection
-                    if num_range.start < mapping.src {
-                        leftover.push_back(num_range.start..mapping.src);
-                    }
+    let min_location = chain
+        .apply_ranges(&ranges)
+        .iter()
+        .map(|r| r.start)
+        .min()
+        .unwrap();
+
+    println!("Min location when using seed range representation: {min_location}");
+
+    let args: Vec<_> = std::env::args().skip(1).collect();
+    // `p5 map <from> <to> <values>`: where values of one category go in another
+    if let [command, from, to, values] = &args[..] {
+        if command == "map" {
+            let values = parse_range(values)?;
+            let mapped = almanac
+                .chain(from, to)?
+                .apply_ranges(std::slice::from_ref(&values));
+            println!("{from} {values:?} -> {to} {mapped:?}");
+        }
+    }
+    // `p5 invert <locations>`: seeds that end up in the given locations
+    if let [command, locations] = &args[..] {
+        if command == "invert" {
+            let locations = parse_range(locations)?;
+            let seeds = chain.preimage(locations.clone());
+            println!("Seeds mapped to locations {locations:?}: {seeds:?}");
 
-                    // num intersecting mapping
-                    let intersection_range = (num_range.start.max(mapping.src))
-                        ..(num_range.end.min(mapping.src + mapping.len));
-                    result.push(
-                        (intersection_range.start + mapping.dst - mapping.src)
-                            ..(intersection_range.end + mapping.dst - mapping.src),
-                    );
-
-                    // suffix of num range with no intersection
-                    if num_range.end > mapping.src + mapping.len {
-                        leftover.push_back((mapping.src + mapping.len)..num_range.end);
+            let mut sorted = ranges.clone();
+            sorted.sort_by_key(|r| r.start);
+            let part2_ranges = merge_ranges(sorted);
+            let mut part2_seeds = vec![];
+            for s in &seeds {
+                for r in &part2_ranges {
+                    if s.start.max(r.start) < s.end.min(r.end) {
+                        part2_seeds.push(s.start.max(r.start)..s.end.min(r.end));
                     }
                 }
-                // if no mapping applies to this leftover, map it 1:1 (put it in result)
-                if result_len == result.len() {
-                    result.push(num_range);
+            }
+            println!("Of which in the seed ranges of part 2: {part2_seeds:?}");
+
+            // cross-check of part 2, going up from the lowest locations
+            if let Some(inverse) = chain.invert() {
+                let upward = inverse.first_mapped_into(&ranges);
+                if upward != Some(min_location) {
+                    return Err(format!(
+                        "searching locations upward gives {upward:?}, not {min_location}"
+                    )
+                    .into());
                 }
+                println!("Min location searching locations upward: {min_location}");
             }
         }
-        // now result contains all the new items produces by processing the ranges. Replace `ranges`
-        // with it.
-        ranges = result;
     }
 
-    // please no duplicates... Otherwise algo is wrong, or mappings overlap (yikes!)
-    let min_location = ranges.iter().map(|r| r.start).min().unwrap();
+    if explore::requested() {
+        explore::repl("p5", &mut Explorer { categories, maps })?;
+    }
+
+    Ok(())
+}
 
-    println!("Min location when using seed range representation: {min_location}");
+struct Explorer<'s> {
+    categories: Vec<&'s str>,
+    maps: Vec<RangeMap>,
+}
+
+impl Explore for Explorer<'_> {
+    const COMMANDS: &'static [Command] = &[Command {
+        name: "lookup",
+        usage: "lookup <seed>",
+        help: "follows a seed through every map",
+    }];
+
+    fn run(&mut self, _name: &str, args: &str) -> anyhow::Result<String> {
+        let mut num: u64 = args.parse().context("expected a seed number")?;
+        let mut out = format!("{} {num}", self.categories[0]);
+        for (map, category) in self.maps.iter().zip(&self.categories[1..]) {
+            num = map
+                .checked_apply(num)
+                .with_context(|| format!("{num} is past the domain of the maps"))?;
+            out += &format!(" -> {category} {num}");
+        }
+        Ok(out)
+    }
+}
+
+/// Joins the overlapping or adjacent ranges, which must be sorted by start
+fn merge_ranges(ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    let mut merged: Vec<Range<u64>> = vec![];
+    for r in ranges {
+        match merged.last_mut() {
+            Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+            _ => merged.push(r),
+        }
+    }
+    merged
+}
+
+/// Parses `a..b` or a single value `a`
+fn parse_range(s: &str) -> anyhow::Result<Range<u64>> {
+    let usage = || format!("expected `<start>..<end>` or `<value>`, got {s:?}");
+    Ok(match s.split_once("..") {
+        Some((start, end)) => {
+            start.trim().parse().with_context(usage)?..end.trim().parse().with_context(usage)?
+        }
+        None => {
+            let value: u64 = s.trim().parse().with_context(usage)?;
+            value..value.checked_add(1).with_context(usage)?
+        }
+    })
+}
+
+/// Ranges of part 2 going through one map per step
+struct RangeSplitting<'a> {
+    categories: &'a [&'a str],
+    /// Ranges after each map, the first ones being the seeds
+    stages: Vec<Vec<Range<u64>>>,
+    current: usize,
+    /// Largest value, to scale the frame
+    max: u64,
+}
+
+impl<'a> RangeSplitting<'a> {
+    const WIDTH: usize = 100;
+
+    fn new(categories: &'a [&'a str], maps: &[RangeMap], seeds: Vec<Range<u64>>) -> Self {
+        let mut stages = vec![seeds];
+        for map in maps {
+            stages.push(map.apply_ranges(stages.last().unwrap()));
+        }
+        let max = stages.iter().flatten().map(|r| r.end).max().unwrap_or(1);
+        Self {
+            categories,
+            stages,
+            current: 0,
+            max,
+        }
+    }
+}
+
+impl Step for RangeSplitting<'_> {
+    fn step(&mut self) -> bool {
+        self.current += 1;
+        self.current < self.stages.len()
+    }
+
+    /// First row is the category name, then one row per range, drawn on a scaled number line.
+    fn frame(&self) -> Frame {
+        let mut ranges = self.stages[self.current].clone();
+        ranges.sort_by_key(|r| r.start);
+        let height = 1 + self.stages.iter().map(Vec::len).max().unwrap();
+        let label: Vec<char> = self.categories[self.current].chars().collect();
+        // column c covers values c * max / WIDTH .. (c + 1) * max / WIDTH
+        let column = |v: u64| (v as u128 * Self::WIDTH as u128 / (self.max as u128 + 1)) as usize;
+
+        let mut frame = Frame::new(Self::WIDTH, height, |x, y| match y {
+            0 => label.get(x).copied().unwrap_or(' '),
+            _ => match ranges.get(y - 1) {
+                Some(r) if (column(r.start)..=column(r.end - 1)).contains(&x) => '#',
+                _ => ' ',
+            },
+        });
+        frame.style_with(|_, y, _| match y {
+            0 => Style::default(),
+            _ => Style::fg(Color::Green),
+        });
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_range, Almanac, Mapping, RangeMap};
+
+    fn map(mappings: &[[u64; 3]]) -> RangeMap {
+        let mappings: Vec<_> = mappings
+            .iter()
+            .map(|&[dst, src, len]| Mapping { src, dst, len })
+            .collect();
+        RangeMap::from_mappings(&mappings).unwrap()
+    }
+
+    /// The maps of the example almanac, from seed to location
+    fn example() -> Vec<RangeMap> {
+        vec![
+            map(&[[50, 98, 2], [52, 50, 48]]),
+            map(&[[0, 15, 37], [37, 52, 2], [39, 0, 15]]),
+            map(&[[49, 53, 8], [0, 11, 42], [42, 0, 7], [57, 7, 4]]),
+            map(&[[88, 18, 7], [18, 25, 70]]),
+            map(&[[45, 77, 23], [81, 45, 19], [68, 64, 13]]),
+            map(&[[0, 69, 1], [1, 0, 69]]),
+            map(&[[60, 56, 37], [56, 93, 4]]),
+        ]
+    }
+
+    #[test]
+    fn apply() {
+        let soil = &example()[0];
+        let applied: Vec<_> = [79, 14, 55, 13, 98, 99, 100]
+            .map(|s| soil.apply(s))
+            .to_vec();
+        assert_eq!(applied, [81, 14, 57, 13, 50, 51, 100]);
+        assert_eq!(soil.apply(u64::MAX - 1), u64::MAX - 1);
+        assert_eq!(soil.checked_apply(u64::MAX), None);
+        assert!(RangeMap::from_mappings(&[Mapping {
+            src: 0,
+            dst: u64::MAX - 1,
+            len: 2
+        }])
+        .is_err());
+        assert_eq!(
+            soil.apply_ranges(&[40..60, 97..101]),
+            [40..50, 52..62, 99..100, 50..52, 100..101]
+        );
+    }
+
+    #[test]
+    fn compose() {
+        let maps = example();
+        let chain = maps
+            .iter()
+            .fold(RangeMap::identity(), |chain, map| chain.compose(map));
+
+        // same as going through each map
+        for seed in 0..200 {
+            let location = maps.iter().fold(seed, |num, map| map.apply(num));
+            assert_eq!(chain.apply(seed), location, "seed {seed}");
+        }
+        // pieces are contiguous
+        assert!(chain
+            .pieces
+            .windows(2)
+            .all(|w| w[0].src + w[0].len == w[1].src));
+
+        // both parts of the example
+        let seeds = [79, 14, 55, 13];
+        assert_eq!(seeds.iter().map(|&s| chain.apply(s)).min(), Some(35));
+        let ranges = chain.apply_ranges(&[79..93, 55..68]);
+        assert_eq!(ranges.iter().map(|r| r.start).min(), Some(46));
+        assert_eq!(ranges.iter().map(|r| r.end - r.start).sum::<u64>(), 14 + 13);
+    }
+
+    #[test]
+    fn inverse() {
+        let maps = example();
+        let chain = maps
+            .iter()
+            .fold(RangeMap::identity(), |chain, map| chain.compose(map));
+
+        // seed 82 is the lowest location of part 2, 46
+        let seeds = chain.preimage(46..47);
+        assert_eq!(seeds, vec![82..83]);
+        assert!(chain.preimage(46..60).iter().any(|r| r.contains(&82)));
+        for seed in chain.preimage(46..60).into_iter().flatten() {
+            assert!((46..60).contains(&chain.apply(seed)));
+        }
+
+        let inverse = chain.invert().unwrap();
+        for location in 0..200 {
+            assert_eq!(chain.apply(inverse.apply(location)), location);
+        }
+        assert_eq!(inverse.compose(&chain), RangeMap::identity());
+
+        // two values mapped to 0
+        assert_eq!(map(&[[0, 5, 1]]).invert(), None);
+        assert_eq!(map(&[[0, 5, 1]]).preimage(0..1), [0..1, 5..6]);
+
+        assert_eq!(inverse.first_mapped_into(&[79..93, 55..68]), Some(46));
+        assert_eq!(inverse.first_mapped_into(&[]), None);
+
+        assert_eq!(parse_range("46..60").unwrap(), 46..60);
+        assert_eq!(parse_range("46").unwrap(), 46..47);
+        assert!(parse_range("46..x").is_err());
+        assert!(parse_range(&u64::MAX.to_string()).is_err());
+    }
+
+    #[test]
+    fn almanac() {
+        // example almanac, maps shuffled
+        let content = "seeds: 79 14 55 13
+
+water-to-light map:
+88 18 7
+18 25 70
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+humidity-to-location map:
+60 56 37
+56 93 4
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+";
+        let almanac = Almanac::parse(content).unwrap();
+        let path = almanac.path("seed", "location").unwrap();
+        assert_eq!(path[..3], ["seed", "soil", "fertilizer"]);
+        assert_eq!(almanac.maps_along(&path), example());
+        let chain = almanac.chain("seed", "location").unwrap();
+        assert_eq!(chain.apply(79), 82);
+
+        // soil 81 is humidity 78 in the example
+        let soil_to_humidity = almanac.chain("soil", "humidity").unwrap();
+        assert_eq!(soil_to_humidity.apply(81), 78);
+        assert_eq!(almanac.chain("soil", "soil").unwrap(), RangeMap::identity());
+        assert!(almanac.chain("location", "seed").is_err());
+        assert!(almanac.chain("seed", "dirt").is_err());
+
+        let error = |content| Almanac::parse(content).err().unwrap().to_string();
+        assert_eq!(error("seeds: 1\n\na-to-b map:\n0 5 3\n9 7 2"), "a-to-b map");
+        assert!(RangeMap::from_mappings(&[
+            Mapping {
+                src: 5,
+                dst: 0,
+                len: 3
+            },
+            Mapping {
+                src: 7,
+                dst: 9,
+                len: 2
+            }
+        ])
+        .is_err());
+        assert_eq!(
+            error("seeds: 1\n\na-to-b map:\n0 5 3\n\nb-to-a map:\n0 5 3"),
+            "maps from a lead back to it"
+        );
+        assert_eq!(
+            error("seeds: 1\n\na-to-b map:\n0 5 3\n\na-to-c map:\n0 5 3"),
+            "several maps from a"
+        );
+    }
 }
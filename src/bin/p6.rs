@@ -1,29 +1,230 @@
-use std::fs;
+use std::{fs, ops::RangeInclusive};
 
-fn main() {
+use anyhow::{bail, Context};
+
+/// How the boat charges while the button is held
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Charge {
+    /// Speed gained per millisecond held
+    acceleration: u64,
+    /// Speed the boat cannot go over
+    max_speed: Option<u64>,
+    /// Shortest allowed hold time
+    min_hold: u64,
+}
+
+impl Default for Charge {
+    /// The puzzle's: 1 mm/ms more per millisecond held
+    fn default() -> Self {
+        Self {
+            acceleration: 1,
+            max_speed: None,
+            min_hold: 0,
+        }
+    }
+}
+
+impl Charge {
+    /// `--acceleration=<n>`, `--max-speed=<n>` and `--min-hold=<ms>`
+    fn from_args() -> anyhow::Result<Self> {
+        let mut charge = Self::default();
+        for arg in std::env::args().skip(1) {
+            if let Some(n) = arg.strip_prefix("--acceleration=") {
+                charge.acceleration = n.parse().context("parsing acceleration")?;
+            } else if let Some(n) = arg.strip_prefix("--max-speed=") {
+                charge.max_speed = Some(n.parse().context("parsing max speed")?);
+            } else if let Some(n) = arg.strip_prefix("--min-hold=") {
+                charge.min_hold = n.parse().context("parsing min hold time")?;
+            }
+        }
+        Ok(charge)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Race {
+    time: u64,
+    record: u64,
+    charge: Charge,
+}
+
+impl Race {
+    fn speed(&self, held: u64) -> u128 {
+        let speed = self.charge.acceleration as u128 * held as u128;
+        match self.charge.max_speed {
+            Some(max) => speed.min(max as u128),
+            None => speed,
+        }
+    }
+
+    /// Distance travelled when holding the button `held` ms, which must be at most the race time
+    fn distance(&self, held: u64) -> u128 {
+        self.speed(held) * (self.time - held) as u128
+    }
+
+    fn beats(&self, held: u64) -> bool {
+        (self.charge.min_hold..=self.time).contains(&held)
+            && self.distance(held) > self.record as u128
+    }
+
+    /// Allowed hold time going the farthest. The distance rises up to it, then falls.
+    fn peak(&self) -> u64 {
+        let time = self.time;
+        // the uncapped distance peaks in the middle, the capped one around where the cap is
+        // reached: just before it, or once the speed is capped
+        let mut candidates = vec![time / 2, time - time / 2];
+        if let Some(max) = self.charge.max_speed {
+            let acceleration = self.charge.acceleration.max(1);
+            candidates.extend([max / acceleration, max.div_ceil(acceleration)]);
+        }
+        candidates
+            .into_iter()
+            .map(|held| held.clamp(self.charge.min_hold.min(time), time))
+            .max_by_key(|&held| self.distance(held))
+            .unwrap()
+    }
+
+    /// Hold times beating the record, exactly
+    fn winning(&self) -> Option<RangeInclusive<u64>> {
+        let peak = self.peak();
+        if !self.beats(peak) {
+            return None;
+        }
+        if self.charge.max_speed.is_none() {
+            // acceleration * k > record <==> k > record / acceleration, k being an integer
+            let first = first_winning(self.time, self.record / self.charge.acceleration)?;
+            return Some(first.max(self.charge.min_hold)..=self.time - first);
+        }
+        // the distance is not a quadratic anymore, but still rises then falls
+        let first = partition_point(self.charge.min_hold, peak, |held| !self.beats(held));
+        let last = partition_point(peak, self.time, |held| held == peak || self.beats(held));
+        let last = if self.beats(last) { last } else { last - 1 };
+        Some(first..=last)
+    }
+
+    fn ways(&self) -> u64 {
+        self.winning().map_or(0, |r| r.end() - r.start() + 1)
+    }
+
+    /// Speed and distance for every hold time, winning ones marked with `*`
+    fn table(&self) -> String {
+        let mut table = format!("held\tspeed\tdistance (record {})\n", self.record);
+        for held in self.charge.min_hold..=self.time {
+            let mark = if self.beats(held) { "\t*" } else { "" };
+            table += &format!(
+                "{held}\t{}\t{}{mark}\n",
+                self.speed(held),
+                self.distance(held)
+            );
+        }
+        table
+    }
+}
+
+/// Amount of held times beating the record `distance` in a race lasting `time`. Exact: computed
+/// in `u128` with an integer square root, and ties with the record do not count.
+fn ways(time: u64, distance: u64) -> u64 {
+    let (time, distance) = (time as u128, distance as u128);
+    let beats = |held: u128| held * (time - held) > distance;
+    if time * time <= 4 * distance {
+        return 0;
+    }
+    // the lower root is (time - sqrt(delta)) / 2, the square root being rounded down at most by
+    // one, the first winning held time is at most two steps away
+    let delta = time * time - 4 * distance;
+    let mut held = (time - delta.isqrt()) / 2;
+    while held > 0 && beats(held - 1) {
+        held -= 1;
+    }
+    while held <= time / 2 && !beats(held) {
+        held += 1;
+    }
+    if held > time / 2 {
+        return 0;
+    }
+    // symmetric around time / 2: the last winning held time is `time - held`
+    (time - 2 * held + 1) as u64
+}
+
+/// Lowest hold time beating `record` with the puzzle's charge in a race lasting `time`. The
+/// winning hold times are symmetric around `time / 2`.
+fn first_winning(time: u64, record: u64) -> Option<u64> {
+    let ways = ways(time, record);
+    (ways > 0).then(|| (time - ways).div_ceil(2))
+}
+
+/// First value in `start..=end` for which `pred` is false, or `end` if there is none. `pred`
+/// must be true, then false.
+fn partition_point(mut start: u64, mut end: u64, pred: impl Fn(u64) -> bool) -> u64 {
+    while start < end {
+        let mid = start + (end - start) / 2;
+        if pred(mid) {
+            start = mid + 1;
+        } else {
+            end = mid;
+        }
+    }
+    start
+}
+
+/// How the sheet is read
+#[derive(Clone, Copy, Debug)]
+enum Reading {
+    /// One race per column
+    Races,
+    /// A single race, ignoring the spaces between the digits
+    Kerning,
+}
+
+/// The races sheet: a line of times and one of records
+struct Sheet<'s> {
+    times: Vec<&'s str>,
+    records: Vec<&'s str>,
+}
+
+impl<'s> Sheet<'s> {
+    fn parse(content: &'s str) -> anyhow::Result<Self> {
+        let mut lines = content.lines();
+        let mut line = |prefix: &str| {
+            lines
+                .next()
+                .and_then(|l| l.strip_prefix(prefix))
+                .map(|l| l.split_whitespace().collect::<Vec<_>>())
+                .with_context(|| format!("expected a line starting with {prefix:?}"))
+        };
+        let times = line("Time:")?;
+        let records = line("Distance:")?;
+        if times.len() != records.len() {
+            bail!("{} times but {} records", times.len(), records.len());
+        }
+        Ok(Self { times, records })
+    }
+
+    fn races(&self, reading: Reading, charge: Charge) -> anyhow::Result<Vec<Race>> {
+        let race = |time: &str, record: &str| -> anyhow::Result<Race> {
+            Ok(Race {
+                time: time.parse().context("parsing race time")?,
+                record: record.parse().context("parsing race distance")?,
+                charge,
+            })
+        };
+        match reading {
+            Reading::Races => self
+                .times
+                .iter()
+                .zip(&self.records)
+                .map(|(time, record)| race(time, record))
+                .collect(),
+            Reading::Kerning => Ok(vec![race(&self.times.concat(), &self.records.concat())?]),
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
     let content = fs::read_to_string("inputs/p6/races.txt").expect("reading input file");
-    let mut lines = content.lines();
-
-    // of course this parsing logic is needed for the small input I've got.............
-    // kappachungus maximus deluxe
-    let times: Vec<u64> = lines
-        .next()
-        .expect("getting times line")
-        .strip_prefix("Time:")
-        .unwrap()
-        .split_whitespace()
-        .map(|n| n.parse())
-        .collect::<Result<_, _>>()
-        .expect("parsing race time");
-    let distances: Vec<u64> = lines
-        .next()
-        .expect("getting distances line")
-        .strip_prefix("Distance:")
-        .unwrap()
-        .split_whitespace()
-        .map(|n| n.parse())
-        .collect::<Result<_, _>>()
-        .expect("parsing race distance");
+    let sheet = Sheet::parse(&content)?;
+    let charge = Charge::from_args()?;
+
     // for every millisecond the button is held, the boat's speed increases by 1m/s (mm/ms)
     // Given the time allowed for each race, and the current record, find all the solutions that
     // beat the record (for each race). Note that v = t_held, thus I write speed = held
@@ -36,53 +237,169 @@ fn main() {
     // $$ held = (time +- sqrt(time^2 - 4*distance)) / 2 $$
     // and thus the amount of solutions is just the diff between the two plus 1 (rounding may apply)
 
-    let ways = |time, distance| {
-        // no integer sqrt in std........... balls
-        let delta = ((time * time - 4 * distance) as f64).sqrt();
-        let held1 = (time as f64 + delta) / 2.0;
-        let held2 = (time as f64 - delta) / 2.0;
+    let races = sheet.races(Reading::Races, charge)?;
 
-        (held1.floor() - held2.ceil()) as u64 + 1
-    };
+    // `--table=<race>`: distance for every hold time of a race
+    if let Some(index) = std::env::args().skip(1).find_map(|a| {
+        a.strip_prefix("--table=")
+            .map(|i| i.parse::<usize>().context("parsing race number"))
+    }) {
+        let index = index?;
+        let race = races.get(index).context("no such race")?;
+        print!("{}", race.table());
+        return Ok(());
+    }
 
-    let ways_product: u64 = times
-        .into_iter()
-        .zip(distances)
+    let ways_product: u64 = races
+        .iter()
         .enumerate()
-        .map(|(game_id, (time, distance))| (game_id, ways(time, distance)))
-        .inspect(|(game_id, ways)| {
-            println!("Game {game_id} allows for {ways} ways to beat the record.")
+        .map(|(game_id, race)| {
+            match race.winning() {
+                Some(range) => println!(
+                    "Game {game_id} allows for {} ways to beat the record, holding {range:?} ms.",
+                    race.ways()
+                ),
+                None => println!("Game {game_id} cannot be won."),
+            }
+            race.ways()
         })
-        .map(|a| a.1)
         .product();
 
     println!("[Part 1] Product of ways: {ways_product}");
 
-    // PART 2: Now we get to re-parse... Yay!!
-
-    let mut lines = content.lines();
-    // lmao the functional hell
-    let time = lines
-        .next()
-        .unwrap()
-        .strip_prefix("Time:")
-        .unwrap()
-        .trim()
-        .chars()
-        .filter(|c| c.is_ascii_digit())
-        .collect::<String>();
-    let distance = lines
-        .next()
-        .unwrap()
-        .strip_prefix("Distance:")
-        .unwrap()
-        .trim()
-        .chars()
-        .filter(|c| c.is_ascii_digit())
-        .collect::<String>();
-
-    println!("Time {time:?}, distance {distance:?}");
-    let time = u64::from_str_radix(&time, 10).unwrap();
-    let distance = u64::from_str_radix(&distance, 10).unwrap();
-    println!("[Part 2] Ways: {}", ways(time, distance));
+    // PART 2: same sheet, but with bad kerning
+    let [race] = sheet.races(Reading::Kerning, charge)?[..] else {
+        unreachable!()
+    };
+    println!("Time {}, distance {}", race.time, race.record);
+    println!("[Part 2] Ways: {}", race.ways());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ways, Charge, Race, Reading, Sheet};
+
+    fn race(time: u64, record: u64) -> Race {
+        Race {
+            time,
+            record,
+            charge: Charge::default(),
+        }
+    }
+
+    /// Counts the winning hold times one by one
+    fn brute_force(race: &Race) -> u64 {
+        (0..=race.time).filter(|&held| race.beats(held)).count() as u64
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(ways(7, 9), 4);
+        assert_eq!(ways(15, 40), 8);
+        assert_eq!(ways(30, 200), 9);
+        assert_eq!(ways(71530, 940200), 71503);
+
+        let sheet = Sheet::parse("Time:      7  15   30\nDistance:  9  40  200\n").unwrap();
+        let races = sheet.races(Reading::Races, Charge::default()).unwrap();
+        assert_eq!(races.iter().map(Race::ways).product::<u64>(), 288);
+        assert_eq!(races[0].winning(), Some(2..=5));
+        let race = sheet.races(Reading::Kerning, Charge::default()).unwrap();
+        assert_eq!((race[0].time, race[0].record), (71530, 940200));
+
+        assert!(Sheet::parse("Time: 7 15\nDistance: 9\n").is_err());
+        assert!(Sheet::parse("Distance: 9\n").is_err());
+    }
+
+    #[test]
+    fn perfect_squares() {
+        // holding 2 or 4 ties the record of 8, only 3 beats it
+        assert_eq!(ways(6, 8), 1);
+        // holding 3 ties the record of 9
+        assert_eq!(ways(6, 9), 0);
+        // best is 1 * 2 = 2, which ties
+        assert_eq!(ways(3, 2), 0);
+        assert_eq!(ways(0, 0), 0);
+        assert_eq!(ways(2, 0), 1);
+    }
+
+    #[test]
+    fn brute_force_small() {
+        for time in 0..60 {
+            for distance in 0..(time * time / 4 + 2) {
+                let race = race(time, distance);
+                assert_eq!(race.ways(), brute_force(&race), "{time} {distance}");
+            }
+        }
+    }
+
+    #[test]
+    fn large() {
+        // would overflow u64 when squaring the time
+        let time = u64::MAX;
+        assert_eq!(ways(time, 0), time - 1);
+        let time = (1 << 32) + 1;
+        let best = (1 << 31) * ((1 << 31) + 1);
+        assert_eq!(ways(time, best - 1), 2);
+        assert_eq!(ways(time, best), 0);
+    }
+
+    #[test]
+    fn variants() {
+        let charges = [
+            (2, None, 0),
+            (3, Some(5), 0),
+            (1, Some(4), 2),
+            (1, None, 5),
+            (2, Some(1), 0),
+            (0, None, 0),
+            (1, None, 100),
+            (10, Some(11), 0),
+        ];
+        for (acceleration, max_speed, min_hold) in charges {
+            let charge = Charge {
+                acceleration,
+                max_speed,
+                min_hold,
+            };
+            for time in 0..40 {
+                for record in 0..(time * time / 2 + 2) {
+                    let race = Race {
+                        time,
+                        record,
+                        charge,
+                    };
+                    let brute = brute_force(&race);
+                    assert_eq!(race.ways(), brute, "{charge:?} {time} {record}");
+                    if let Some(range) = race.winning() {
+                        assert!(race.beats(*range.start()) && race.beats(*range.end()));
+                    }
+                }
+            }
+        }
+
+        // capped at 11 mm/ms: holding 1 goes 10 * 9 = 90, holding 2 only 11 * 8 = 88
+        let race = Race {
+            time: 10,
+            record: 89,
+            charge: Charge {
+                acceleration: 10,
+                max_speed: Some(11),
+                min_hold: 0,
+            },
+        };
+        assert_eq!(race.winning(), Some(1..=1));
+
+        // capped at 4 mm/ms: holding 2 goes 10, 3 and 4 go 12, 5 only 8
+        let race = Race {
+            time: 7,
+            record: 9,
+            charge: Charge {
+                max_speed: Some(4),
+                ..Charge::default()
+            },
+        };
+        assert_eq!(race.winning(), Some(2..=4));
+        assert!(race.table().lines().nth(5).unwrap().ends_with("*"));
+    }
 }
@@ -4,8 +4,19 @@
 //! The order of the various hands is the following: 5 > 4 > 3+2 > 3 > 2+2 > 2 > 1
 //! I can use `3 bits` to encode the hand type, and `5*4 == 20` bits to describe each value of the hand,
 //! allowing for a very fast and simple comparison between different hands, and thus sorting.
+//!
+//! Variants of the game (see [`Variant`]) are packed the same way, in a `u32` when they fit and
+//! in a `u64` otherwise.
+
+use std::{
+    fs,
+    ops::{BitOr, Shl},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context};
 
-use std::{fs, str::FromStr};
+use advent_of_code_2023::explore::{self, Command, Explore};
 use thiserror::Error;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -54,257 +65,666 @@ impl<T> Hand<T> {
     }
 }
 
-impl FromStr for Hand<Normal> {
-    type Err = HandParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value_of_card = |card: char| {
-            Some(match card {
-                '2'..='9' => card.to_digit(10).unwrap(), // cannot fail
-                'T' => 10,
-                'J' => 11,
-                'Q' => 12,
-                'K' => 13,
-                'A' => 14,
-                _ => return None,
-            })
-        };
-
-        let err = || HandParseError(s.to_string());
+/// Card values and wildcards of a set of rules, implemented by the marker types of [`Hand`]
+trait Ruleset: Copy + Ord {
+    /// Value of a card in `1..=14`, breaking the ties between hands of the same type
+    fn value(card: char) -> Option<u32>;
 
-        if s.len() != 5 {
-            return Err(err());
-        }
+    /// Whether the card stands for whatever card makes the best hand
+    fn is_wildcard(_card: char) -> bool {
+        false
+    }
 
-        // first obtain all values of the hand. Later use them to derive
-        // the hand type
-        let mut values = s
+    /// `hand` with its wildcards replaced by the card making the best hand: the most frequent
+    /// other card (the strongest among ties), or aces if there is none
+    fn substitute(hand: &str) -> String {
+        let best = hand
             .chars()
-            .try_fold(0, |values, card| {
-                let v = value_of_card(card);
-                v.map(|card_value| values << 4 | card_value)
-            })
-            .ok_or_else(|| HandParseError(s.to_string()))?;
-
-        // naive, but not too naive
-        // process one card at a time. If not seen yet,
-        // count the occurrences and modify the hand type accordingly
-        let mut processed = ['X'; 5];
-        for (i, card) in s.char_indices() {
-            if processed.contains(&card) {
-                continue;
-            }
+            .filter(|&c| !Self::is_wildcard(c))
+            .max_by_key(|&c| (hand.matches(c).count(), Self::value(c)))
+            .unwrap_or('A');
+        hand.chars()
+            .map(|c| if Self::is_wildcard(c) { best } else { c })
+            .collect()
+    }
+}
 
-            let count = 1 + s
-                .char_indices()
-                .filter(move |&(j, other)| j != i && other == card)
-                .count();
+impl Ruleset for Normal {
+    fn value(card: char) -> Option<u32> {
+        Some(match card {
+            '2'..='9' => card.to_digit(10).unwrap(), // cannot fail
+            'T' => 10,
+            'J' => 11,
+            'Q' => 12,
+            'K' => 13,
+            'A' => 14,
+            _ => return None,
+        })
+    }
+}
 
-            let cur_hand_type = (values >> (5 * 4)) as u8;
+impl Ruleset for Joker {
+    /// Jokers are the weakest cards
+    fn value(card: char) -> Option<u32> {
+        match card {
+            'J' => Some(1),
+            _ => Normal::value(card),
+        }
+    }
 
-            let new_hand_type = match (count, cur_hand_type.into()) {
-                (2, HandType::ThreeOfAKind) | (2, HandType::Pair) => cur_hand_type + 1, // adds a pair to current value
-                (3, HandType::Pair) => HandType::FullHouse as u8,
-                (5, _) => HandType::FiveOfAKind as u8,
-                (4, _) => HandType::FourOfAKind as u8,
-                (3, _) => HandType::ThreeOfAKind as u8,
-                (2, _) => HandType::Pair as u8,
-                _ => cur_hand_type,
-            };
+    fn is_wildcard(card: char) -> bool {
+        card == 'J'
+    }
+}
 
-            values = (values & 0xFFFFF) | (new_hand_type as u32) << (5 * 4);
+/// Sizes of the groups of equal cards, largest first. Wildcards always do best joining the
+/// largest group.
+fn groups(cards: impl IntoIterator<Item = char>, is_wildcard: impl Fn(char) -> bool) -> Vec<usize> {
+    // occurrences of every card, wildcards set apart
+    let mut counts: Vec<(char, usize)> = vec![];
+    let mut wildcards = 0;
+    for card in cards {
+        if is_wildcard(card) {
+            wildcards += 1;
+        } else if let Some((_, count)) = counts.iter_mut().find(|(c, _)| *c == card) {
+            *count += 1;
+        } else {
+            counts.push((card, 1));
+        }
+    }
+    let mut groups: Vec<usize> = counts.into_iter().map(|(_, count)| count).collect();
+    groups.sort_unstable_by(|a, b| b.cmp(a));
+    match groups.first_mut() {
+        Some(largest) => *largest += wildcards,
+        None => groups.push(wildcards),
+    }
+    groups
+}
 
-            processed[i] = card;
+impl HandType {
+    /// Type of a hand of five cards, given its [`groups`]
+    fn classify(groups: &[usize]) -> Self {
+        let first = groups.first().copied().unwrap_or(0);
+        let second = groups.get(1).copied().unwrap_or(0);
+        match (first, second) {
+            (5, _) => HandType::FiveOfAKind,
+            (4, _) => HandType::FourOfAKind,
+            (3, 2) => HandType::FullHouse,
+            (3, _) => HandType::ThreeOfAKind,
+            (2, 2) => HandType::TwoPair,
+            (2, _) => HandType::Pair,
+            _ => HandType::One,
         }
-        Ok(Hand(values, Default::default()))
     }
 }
 
-impl FromStr for Hand<Joker> {
+impl<R: Ruleset> FromStr for Hand<R> {
     type Err = HandParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value_of_card = |card: char| {
-            Some(match card {
-                '2'..='9' => card.to_digit(10).unwrap(), // cannot fail
-                'T' => 10,
-                'J' => 1,
-                'Q' => 12,
-                'K' => 13,
-                'A' => 14,
-                _ => return None,
-            })
-        };
-
-        let err = || HandParseError(s.to_string());
-
-        if s.len() != 5 {
-            return Err(err());
+        if s.chars().count() != 5 {
+            return Err(HandParseError(s.to_string()));
         }
 
         // first obtain all values of the hand. Later use them to derive
         // the hand type
-        let mut values = s
+        let values = s
             .chars()
             .try_fold(0, |values, card| {
-                let v = value_of_card(card);
-                v.map(|card_value| values << 4 | card_value)
+                R::value(card).map(|card_value| values << 4 | card_value)
             })
             .ok_or_else(|| HandParseError(s.to_string()))?;
 
-        // naive, but not too naive
-        // process one card at a time. If not seen yet,
-        // count the occurrences and modify the hand type accordingly
-        // Now the joker cards become an issue! We do a second pass LATER to only those, and adjust
-        // the hand type accordingly
-        let mut processed = ['X'; 5];
-        for (i, card) in s.char_indices() {
-            // Skip already processed card values AND the joker cards. Joker cards need to be
-            // considered later, in the second pass
-            if card == 'J' || processed.contains(&card) {
-                continue;
-            }
+        let hand_type = HandType::classify(&groups(s.chars(), R::is_wildcard));
 
-            // Count same cards (can be joker!)
-            let count = 1 + s
-                .char_indices()
-                .filter(move |&(j, other)| j != i && other == card)
-                .count();
+        Ok(Hand(
+            values | (hand_type as u32) << (5 * 4),
+            Default::default(),
+        ))
+    }
+}
 
-            let cur_hand_type = (values >> (5 * 4)) as u8;
+/// Integer the hands of a variant are packed in
+trait Key: Ord + Copy + From<u32> + Shl<u32, Output = Self> + BitOr<Output = Self> {
+    const BITS: u32;
+}
 
-            let new_hand_type = match (count, cur_hand_type.into()) {
-                (2, HandType::ThreeOfAKind) | (2, HandType::Pair) => cur_hand_type + 1, // adds a pair to current value
-                (3, HandType::Pair) => HandType::FullHouse as u8,
-                (5, _) => HandType::FiveOfAKind as u8,
-                (4, _) => HandType::FourOfAKind as u8,
-                (3, _) => HandType::ThreeOfAKind as u8,
-                (2, _) => HandType::Pair as u8,
-                _ => cur_hand_type,
-            };
+impl Key for u32 {
+    const BITS: u32 = u32::BITS;
+}
 
-            values = (values & 0xFFFFF) | (new_hand_type as u32) << (5 * 4);
+impl Key for u64 {
+    const BITS: u32 = u64::BITS;
+}
+
+/// Rules of a Camel Cards variant, chosen at runtime
+#[derive(Debug, Clone)]
+struct Variant {
+    /// Cards from weakest to strongest, the wildcards first
+    order: Vec<char>,
+    /// Amount of wildcards, at the start of `order`
+    wildcards: usize,
+    hand_size: usize,
+    /// Cards are written `<rank><suit>`, and flushes (all cards of one suit) beat the hands with
+    /// the same groups of cards
+    suits: bool,
+    /// Groups of equal cards of every hand type, largest group first, weakest type first
+    types: Vec<Vec<usize>>,
+}
+
+impl Variant {
+    const ORDER: &'static str = "23456789TJQKA";
 
-            processed[i] = card;
+    /// `wildcards` become the weakest cards, the others keep their `order`
+    fn new(order: &str, wildcards: &str, hand_size: usize, suits: bool) -> anyhow::Result<Self> {
+        let mut cards: Vec<char> = wildcards.chars().collect();
+        cards.extend(order.chars().filter(|c| !wildcards.contains(*c)));
+        if (1..cards.len()).any(|i| cards[..i].contains(&cards[i])) {
+            bail!("cards {order:?} and wildcards {wildcards:?} repeat a card");
+        }
+        if cards.is_empty() || hand_size == 0 {
+            bail!("a variant needs cards and hands of at least one card");
+        }
+        let mut variant = Self {
+            order: cards,
+            wildcards: wildcards.chars().count(),
+            hand_size,
+            suits,
+            types: vec![],
+        };
+        // there are a lot of hand types for large hands, count them before listing them
+        let fits = hand_size <= u64::BITS as usize && {
+            let types = partition_count(hand_size) << suits as u32;
+            hand_size as u32 * variant.card_bits() + width(types - 1) <= u64::BITS
+        };
+        if !fits {
+            bail!("hands of {hand_size} cards do not fit in 64 bits");
         }
+        variant.types = partitions(hand_size, hand_size);
+        variant.types.sort();
+        Ok(variant)
+    }
 
-        let joker_count = s.chars().filter(move |&c| c == 'J').count();
-        let cur_hand_type = ((values >> (5 * 4)) as u8).into();
-
-        let joker_hand_type = match (joker_count, cur_hand_type) {
-            (0, a) => a,
-            // One joker: cur_hand_type cannot be FullHouse or FiveOfAKind
-            (1, HandType::FourOfAKind) => HandType::FiveOfAKind,
-            (1, HandType::ThreeOfAKind) => HandType::FourOfAKind,
-            (1, HandType::TwoPair) => HandType::FullHouse,
-            (1, HandType::Pair) => HandType::ThreeOfAKind,
-            (1, HandType::One) => HandType::Pair,
-            // Two jokers: cur_hand_type cannot be 2+3, 5, 4, 2+2
-            (2, HandType::ThreeOfAKind) => HandType::FiveOfAKind,
-            (2, HandType::Pair) => HandType::FourOfAKind,
-            (2, HandType::One) => HandType::ThreeOfAKind,
-            // Three jokers: cur_hand_type cannot be 2+3, 5, 4, 3, 2+2
-            (3, HandType::Pair) => HandType::FiveOfAKind,
-            (3, HandType::One) => HandType::FourOfAKind,
-            // 4 jokers allow only one other card, easy
-            (4, HandType::One) => HandType::FiveOfAKind,
-            // hand type does not allow encoding zero cards... Oh well :D
-            (5, _) => HandType::FiveOfAKind,
-            _ => {
-                unreachable!("All other combinations are impossible, since there are only 5 cards")
+    /// Variant given by `--cards=<weakest to strongest>`, `--hand-size=<n>` and `--suits`, with
+    /// the `--wildcards=<cards>` (jokers by default) in part 2, or `None` for the puzzle's rules
+    fn from_args(part2: bool) -> anyhow::Result<Option<Self>> {
+        let (mut order, mut wildcards, mut hand_size, mut suits) =
+            (Self::ORDER.to_string(), "J".to_string(), 5, false);
+        let mut custom = false;
+        for arg in std::env::args().skip(1) {
+            if let Some(cards) = arg.strip_prefix("--cards=") {
+                order = cards.to_string();
+            } else if let Some(cards) = arg.strip_prefix("--wildcards=") {
+                wildcards = cards.to_string();
+            } else if let Some(size) = arg.strip_prefix("--hand-size=") {
+                hand_size = size.parse().context("parsing hand size")?;
+            } else if arg == "--suits" {
+                suits = true;
+            } else {
+                continue;
             }
+            custom = true;
+        }
+        if !part2 {
+            wildcards.clear();
+        }
+        custom
+            .then(|| Self::new(&order, &wildcards, hand_size, suits))
+            .transpose()
+    }
+
+    fn card_bits(&self) -> u32 {
+        // values start at 1, like the puzzle's
+        width(self.order.len())
+    }
+
+    fn type_bits(&self) -> u32 {
+        width((self.types.len() << self.suits as u32) - 1)
+    }
+
+    fn bits(&self) -> u32 {
+        self.type_bits() + self.hand_size as u32 * self.card_bits()
+    }
+
+    /// Type index, weakest first, and card values of `hand`
+    fn classify(&self, hand: &str) -> anyhow::Result<(u32, Vec<u32>)> {
+        let chars: Vec<char> = hand.chars().collect();
+        let cards: Vec<_> = match self.suits {
+            true => chars.chunks(2).map(|c| (c[0], c.get(1).copied())).collect(),
+            false => chars.iter().map(|&c| (c, None)).collect(),
         };
+        if cards.len() != self.hand_size
+            || cards.iter().any(|(_, suit)| self.suits != suit.is_some())
+        {
+            bail!("expected {} cards, got {hand:?}", self.hand_size);
+        }
 
-        values = (values & 0xFFFFF) | (joker_hand_type as u32) << (5 * 4);
+        let mut values = vec![];
+        let mut suit = None;
+        let mut flush = true;
+        for &(card, card_suit) in &cards {
+            let index = self
+                .order
+                .iter()
+                .position(|&c| c == card)
+                .with_context(|| format!("unknown card {card:?} in {hand:?}"))?;
+            values.push(index as u32 + 1);
+            // wildcards are of any suit
+            if index >= self.wildcards {
+                flush &= *suit.get_or_insert(card_suit) == card_suit;
+            }
+        }
 
-        Ok(Hand(values, Default::default()))
+        let wildcards = &self.order[..self.wildcards];
+        let groups = groups(cards.iter().map(|&(card, _)| card), |c| {
+            wildcards.contains(&c)
+        });
+        let index = self.types.binary_search(&groups).unwrap() as u32;
+        match self.suits {
+            true => Ok((index << 1 | flush as u32, values)),
+            false => Ok((index, values)),
+        }
+    }
+
+    /// `hand` packed in `K`: its type, then the value of each card
+    fn key<K: Key>(&self, hand: &str) -> anyhow::Result<K> {
+        assert!(self.bits() <= K::BITS, "hands do not fit the key");
+        let (hand_type, values) = self.classify(hand)?;
+        Ok(values.into_iter().fold(K::from(hand_type), |key, value| {
+            key << self.card_bits() | K::from(value)
+        }))
+    }
+
+    /// Sum of the bids times the ranks, the hands packed in the smallest integer they fit
+    fn winnings(&self, hands: &[(&str, u32)]) -> anyhow::Result<u64> {
+        match self.bits() <= u32::BITS {
+            true => self.winnings_with::<u32>(hands),
+            false => self.winnings_with::<u64>(hands),
+        }
+    }
+
+    fn winnings_with<K: Key>(&self, hands: &[(&str, u32)]) -> anyhow::Result<u64> {
+        let mut keys = hands
+            .iter()
+            .map(|&(hand, bid)| Ok((self.key::<K>(hand)?, bid)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        keys.sort_unstable_by_key(|&(key, _)| key);
+        Ok(keys
+            .iter()
+            .enumerate()
+            .map(|(i, &(_, bid))| (i as u64 + 1) * bid as u64)
+            .sum())
     }
 }
 
-fn main() {
-    let content = fs::read_to_string("inputs/p7/hands.txt").expect("reading input file");
+/// Bits needed to write the values up to `max`
+fn width(max: usize) -> u32 {
+    usize::BITS - max.leading_zeros()
+}
 
-    let mut hands_bids: Vec<(Hand<Normal>, u32)> = content
-        .lines()
-        .map(|line| line.split_whitespace())
-        .map(|mut line| line.next().zip(line.next()).unwrap())
-        .map(|(hand_str, bid_str)| (Hand::from_str(hand_str).unwrap(), bid_str.parse().unwrap()))
-        .collect();
+/// Number of [`partitions`] of `n` cards, without listing them
+fn partition_count(n: usize) -> usize {
+    // ways[k]: ways of splitting k cards in groups of the sizes seen so far
+    let mut ways = vec![0; n + 1];
+    ways[0] = 1;
+    for size in 1..=n {
+        for k in size..=n {
+            ways[k] += ways[k - size];
+        }
+    }
+    ways[n]
+}
 
-    hands_bids.sort_by_key(|(hand, _)| *hand);
+/// Ways of splitting `n` cards in groups of at most `max` equal cards, largest group first
+fn partitions(n: usize, max: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut result = vec![];
+    for first in 1..=n.min(max) {
+        for mut rest in partitions(n - first, first) {
+            rest.insert(0, first);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// Parsed hand, as written, and its bid
+type Bid<'s, R> = (Hand<R>, &'s str, u32);
+
+/// Hands and bids sorted by strength, weakest first (rank 1)
+fn ranked<'s, R: Ruleset>(hands: &[(&'s str, u32)]) -> Result<Vec<Bid<'s, R>>, HandParseError> {
+    let mut ranked = hands
+        .iter()
+        .map(|&(hand, bid)| Ok((Hand::<R>::from_str(hand)?, hand, bid)))
+        .collect::<Result<Vec<_>, _>>()?;
+    ranked.sort_by_key(|(hand, _, _)| *hand);
+    Ok(ranked)
+}
 
+/// Sum of the bids times the ranks
+fn winnings<R: Ruleset>(hands: &[(&str, u32)]) -> Result<u32, HandParseError> {
     // multiply bid by rank (weakest is rank 1, increasing)
-    let tot_score: u32 = hands_bids
+    Ok(ranked::<R>(hands)?
         .iter()
         .enumerate()
-        .map(|(i, (_hand, bid))| (i as u32 + 1) * *bid)
-        .sum();
-    println!("[PART 1] Sum of (rank * bid) = {tot_score}");
+        .map(|(i, (_hand, _, bid))| (i as u32 + 1) * *bid)
+        .sum())
+}
+
+/// Every hand by rank, with its type, wildcard substitution and winnings, then a histogram of
+/// the hand types
+fn report<R: Ruleset>(hands: &[(&str, u32)]) -> Result<String, HandParseError> {
+    let ranked = ranked::<R>(hands)?;
+    let mut out = String::from("rank\thand\tas\ttype\t\tbid\twinnings\n");
+    let mut histogram = [0; 7];
+    let mut total = 0;
+    for (i, (hand, cards, bid)) in ranked.iter().enumerate() {
+        let rank = i as u32 + 1;
+        let substitution = R::substitute(cards);
+        let substitution = match substitution == *cards {
+            true => "-".to_string(),
+            false => substitution,
+        };
+        let hand_type = format!("{:?}", hand.hand_type());
+        out += &format!(
+            "{rank}\t{cards}\t{substitution}\t{hand_type:<12}\t{bid}\t{}\n",
+            rank * bid
+        );
+        histogram[hand.hand_type() as usize] += 1;
+        total += rank * bid;
+    }
+    out += &format!("Total\t\t\t\t\t\t{total}\n\n");
+    for (hand_type, count) in histogram.into_iter().enumerate().rev() {
+        let hand_type = format!("{:?}", HandType::from(hand_type as u8));
+        let line = format!("{hand_type:<12} {count:>5} {}", "#".repeat(count));
+        out += line.trim_end();
+        out.push('\n');
+    }
+    Ok(out)
+}
 
-    let mut hands_bids: Vec<(Hand<Joker>, u32)> = content
+fn main() -> anyhow::Result<()> {
+    let content = fs::read_to_string("inputs/p7/hands.txt").expect("reading input file");
+    let hands: Vec<(&str, u32)> = content
         .lines()
-        .map(|line| line.split_whitespace())
-        .map(|mut line| line.next().zip(line.next()).unwrap())
-        .map(|(hand_str, bid_str)| (Hand::from_str(hand_str).unwrap(), bid_str.parse().unwrap()))
-        .collect();
+        .map(|line| {
+            let (hand, bid) = line
+                .split_once(' ')
+                .with_context(|| format!("expected `<hand> <bid>`, got {line:?}"))?;
+            let bid = bid
+                .trim()
+                .parse()
+                .with_context(|| format!("parsing bid of {line:?}"))?;
+            Ok((hand, bid))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let variant = Variant::from_args(false)?;
+
+    if let Some(arg) = std::env::args().find(|a| a.starts_with("--report")) {
+        if variant.is_some() {
+            bail!(
+                "`--report` ranks hands with the puzzle's rules, it cannot be used with variants"
+            );
+        }
+        match arg.as_str() {
+            "--report" | "--report=normal" => print!("{}", report::<Normal>(&hands)?),
+            "--report=joker" => print!("{}", report::<Joker>(&hands)?),
+            _ => bail!("expected `--report[=normal|joker]`, got {arg:?}"),
+        }
+        return Ok(());
+    }
 
-    hands_bids.sort_by_key(|(hand, _)| *hand);
+    // custom rules, for both parts
+    if let Some(variant) = variant {
+        let jokers = Variant::from_args(true)?.unwrap();
+        println!("Hands packed in {} bits", variant.bits().max(jokers.bits()));
+        println!(
+            "[PART 1] Sum of (rank * bid) = {}",
+            variant.winnings(&hands)?
+        );
+        println!("[PART 2] With wildcards = {}", jokers.winnings(&hands)?);
+        return Ok(());
+    }
 
-    // multiply bid by rank (weakest is rank 1, increasing)
-    let tot_score: u32 = hands_bids
-        .iter()
-        .enumerate()
-        .map(|(i, (_hand, bid))| (i as u32 + 1) * *bid)
-        .sum();
-    println!("[PART 2] This is horrible! Result = {tot_score}");
+    println!(
+        "[PART 1] Sum of (rank * bid) = {}",
+        winnings::<Normal>(&hands)?
+    );
+    println!(
+        "[PART 2] This is horrible! Result = {}",
+        winnings::<Joker>(&hands)?
+    );
+
+    if explore::requested() {
+        explore::repl("p7", &mut Explorer { hands })?;
+    }
+
+    Ok(())
+}
+
+struct Explorer<'s> {
+    /// Hands and bids of the input
+    hands: Vec<(&'s str, u32)>,
+}
+
+impl Explorer<'_> {
+    /// Rank of the hand among the input ones (weakest is 1)
+    fn rank<R: Ruleset>(&self, hand: Hand<R>) -> usize {
+        1 + self
+            .hands
+            .iter()
+            .filter_map(|(h, _)| Hand::<R>::from_str(h).ok())
+            .filter(|other| other < &hand)
+            .count()
+    }
+}
+
+impl Explore for Explorer<'_> {
+    const COMMANDS: &'static [Command] = &[Command {
+        name: "hand",
+        usage: "hand <cards>",
+        help: "classifies a hand with and without jokers",
+    }];
+
+    fn run(&mut self, _name: &str, args: &str) -> anyhow::Result<String> {
+        let normal = Hand::<Normal>::from_str(args)?;
+        let joker = Hand::<Joker>::from_str(args)?;
+        let mut out = format!(
+            "normal: {:?} ({:#07x})\njoker:  {:?} ({:#07x})",
+            normal.hand_type(),
+            normal.0,
+            joker.hand_type(),
+            joker.0
+        );
+        if let Some((_, bid)) = self.hands.iter().find(|(h, _)| *h == args) {
+            out += &format!(
+                "\nbid {bid}, rank {} with normal rules, {} with jokers",
+                self.rank(normal),
+                self.rank(joker)
+            );
+        }
+        Ok(out)
+    }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use std::str::FromStr;
-
-//     use crate::{Hand, HandType};
-
-//     #[test]
-//     fn hand_from_str() {
-//         let values = &[
-//             ("", None),
-//             ("2", None),
-//             ("11111", None),
-//             ("22221", None),
-//             (
-//                 "22222",
-//                 Some(Hand(((HandType::FiveOfAKind as u32) << 5 * 4) | 0x22222)),
-//             ),
-//             (
-//                 "99999",
-//                 Some(Hand(((HandType::FiveOfAKind as u32) << 5 * 4) | 0x99999)),
-//             ),
-//             (
-//                 "AAAAA",
-//                 Some(Hand(((HandType::FiveOfAKind as u32) << 5 * 4) | 0xEEEEE)),
-//             ),
-//             (
-//                 "AAAA2",
-//                 Some(Hand(((HandType::FourOfAKind as u32) << 5 * 4) | 0xEEEE2)),
-//             ),
-//             (
-//                 "22233",
-//                 Some(Hand(((HandType::FullHouse as u32) << 5 * 4) | 0x22233)),
-//             ),
-//             (
-//                 "22333",
-//                 Some(Hand(((HandType::FullHouse as u32) << 5 * 4) | 0x22333)),
-//             ),
-//         ][..];
-
-//         for &(input, expected) in values {
-//             let actual = Hand::from_str(input).ok();
-//             assert_eq!(
-//                 expected, actual,
-//                 "parsing hand {:?} did not produce {:x?} as expected, but instead I got {:x?}",
-//                 input, expected, actual
-//             );
-//         }
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        partition_count, report, winnings, Hand, HandType, Joker, Normal, Ruleset, Variant,
+    };
+
+    fn hand<T>(hand_type: HandType, values: u32) -> Option<Hand<T>> {
+        Some(Hand(
+            ((hand_type as u32) << (5 * 4)) | values,
+            Default::default(),
+        ))
+    }
+
+    #[test]
+    fn hand_from_str() {
+        let values = &[
+            ("", None),
+            ("2", None),
+            ("11111", None),
+            ("22221", None),
+            ("2222222", None),
+            ("22222", hand(HandType::FiveOfAKind, 0x22222)),
+            ("99999", hand(HandType::FiveOfAKind, 0x99999)),
+            ("AAAAA", hand(HandType::FiveOfAKind, 0xEEEEE)),
+            ("AAAA2", hand(HandType::FourOfAKind, 0xEEEE2)),
+            ("22233", hand(HandType::FullHouse, 0x22233)),
+            ("22333", hand(HandType::FullHouse, 0x22333)),
+            ("T55J5", hand(HandType::ThreeOfAKind, 0xA55B5)),
+            ("KTJJT", hand(HandType::TwoPair, 0xDABBA)),
+            ("32T3K", hand(HandType::Pair, 0x32A3D)),
+            ("23456", hand(HandType::One, 0x23456)),
+        ][..];
+
+        for &(input, expected) in values {
+            let actual = Hand::<Normal>::from_str(input).ok();
+            assert_eq!(
+                expected, actual,
+                "parsing hand {:?} did not produce {:x?} as expected, but instead I got {:x?}",
+                input, expected, actual
+            );
+        }
+    }
+
+    #[test]
+    fn joker_hand_from_str() {
+        let values = &[
+            ("", None),
+            ("JJJJ", None),
+            ("JJJJJ", hand(HandType::FiveOfAKind, 0x11111)),
+            ("JJJJ2", hand(HandType::FiveOfAKind, 0x11112)),
+            ("JJJ23", hand(HandType::FourOfAKind, 0x11123)),
+            ("JJ234", hand(HandType::ThreeOfAKind, 0x11234)),
+            ("J2345", hand(HandType::Pair, 0x12345)),
+            ("J2233", hand(HandType::FullHouse, 0x12233)),
+            ("J2223", hand(HandType::FourOfAKind, 0x12223)),
+            ("JJ223", hand(HandType::FourOfAKind, 0x11223)),
+            ("T55J5", hand(HandType::FourOfAKind, 0xA5515)),
+            ("KTJJT", hand(HandType::FourOfAKind, 0xDA11A)),
+            ("QQQJA", hand(HandType::FourOfAKind, 0xCCC1E)),
+            ("32T3K", hand(HandType::Pair, 0x32A3D)),
+            ("AAAA2", hand(HandType::FourOfAKind, 0xEEEE2)),
+        ][..];
+
+        for &(input, expected) in values {
+            let actual = Hand::<Joker>::from_str(input).ok();
+            assert_eq!(expected, actual, "parsing hand {input:?} with jokers");
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        let parse = |s| Hand::<Joker>::from_str(s).unwrap();
+        // jokers are the weakest for ties
+        assert!(parse("JKKK2") < parse("QQQQ2"));
+        assert!(parse("J2345") < parse("22345"));
+        let parse = |s| Hand::<Normal>::from_str(s).unwrap();
+        assert!(parse("2AAAA") < parse("33332"));
+        assert!(parse("KK677") > parse("KTJJT"));
+    }
+
+    const EXAMPLE: [(&str, u32); 5] = [
+        ("32T3K", 765),
+        ("T55J5", 684),
+        ("KK677", 28),
+        ("KTJJT", 220),
+        ("QQQJA", 483),
+    ];
+
+    #[test]
+    fn variants() {
+        // the puzzle's rules
+        let normal = Variant::new(Variant::ORDER, "", 5, false).unwrap();
+        let jokers = Variant::new(Variant::ORDER, "J", 5, false).unwrap();
+        assert_eq!(normal.types.len(), 7);
+        assert_eq!((normal.bits(), jokers.bits()), (23, 23));
+        assert_eq!(normal.winnings(&EXAMPLE).unwrap(), 6440);
+        assert_eq!(jokers.winnings(&EXAMPLE).unwrap(), 5905);
+        for hand in ["T55J5", "KTJJT", "JJJJJ", "J2233", "23456"] {
+            let (hand_type, _) = normal.classify(hand).unwrap();
+            let parsed = Hand::<Normal>::from_str(hand).unwrap().hand_type();
+            assert_eq!(hand_type, parsed as u32, "{hand}");
+            let (hand_type, _) = jokers.classify(hand).unwrap();
+            let parsed = Hand::<Joker>::from_str(hand).unwrap().hand_type();
+            assert_eq!(hand_type, parsed as u32, "{hand} with jokers");
+        }
+
+        // both J and 2 wild: T55J5 and K2JJT are four of a kind, 22JJ2 five
+        let wild = Variant::new(Variant::ORDER, "J2", 5, false).unwrap();
+        assert_eq!(
+            wild.classify("T55J5").unwrap().0,
+            HandType::FourOfAKind as u32
+        );
+        assert_eq!(
+            wild.classify("K2JJT").unwrap().0,
+            HandType::FourOfAKind as u32
+        );
+        assert_eq!(
+            wild.classify("22JJ2").unwrap().0,
+            HandType::FiveOfAKind as u32
+        );
+        assert!(wild.key::<u32>("J3333").unwrap() < wild.key::<u32>("23333").unwrap());
+        assert!(wild.key::<u32>("23333").unwrap() < wild.key::<u32>("33332").unwrap());
+
+        // reversed order: 2 is the strongest card
+        let reversed = Variant::new("AKQJT98765432", "", 5, false).unwrap();
+        assert!(reversed.key::<u32>("22345").unwrap() > reversed.key::<u32>("AA345").unwrap());
+
+        // 7 cards still fit a u32: 15 types and 7 cards of 4 bits. With suits, flushes double
+        // the types and need a u64
+        let seven = Variant::new(Variant::ORDER, "", 7, false).unwrap();
+        assert_eq!((seven.types.len(), seven.bits()), (15, 32));
+        assert_eq!(partition_count(7), 15);
+        let seven = Variant::new(Variant::ORDER, "J", 7, true).unwrap();
+        assert_eq!(seven.bits(), 33);
+        let hands = [("2h3h4h5h6h7h8h", 1), ("2h2s4h5h6h7h8h", 10)];
+        // the pair beats the flush without groups
+        assert_eq!(seven.winnings(&hands).unwrap(), 21);
+        let flush = seven.key::<u64>("2h2h4h5h6h7h8h").unwrap();
+        assert!(flush > seven.key::<u64>("2h2s4h5h6h7h8h").unwrap());
+        assert!(flush < seven.key::<u64>("2h2s2h5h6h7h8h").unwrap());
+        // a wildcard of another suit keeps the flush
+        assert!(seven.key::<u64>("2h2hJs5h6h7h8h").unwrap() > flush);
+
+        assert!(Variant::new(Variant::ORDER, "", 20, false).is_err());
+        assert!(Variant::new(Variant::ORDER, "", 90, false).is_err());
+        assert!(Variant::new(Variant::ORDER, "", 2_000_000_000, false).is_err());
+        assert!(Variant::new(Variant::ORDER, "", usize::MAX, false).is_err());
+        // 1 bit per card, but 1741630 types
+        assert_eq!(partition_count(64), 1741630);
+        assert!(Variant::new("2", "", 64, false).is_err());
+        assert!(Variant::new("2234", "", 5, false).is_err());
+        assert!(normal.classify("2345").is_err());
+        assert!(normal.classify("2345X").is_err());
+        assert!(seven.classify("2h3h4h5h6h7h8").is_err());
+    }
+
+    #[test]
+    fn ranking_report() {
+        assert_eq!(winnings::<Normal>(&EXAMPLE).unwrap(), 6440);
+        assert_eq!(winnings::<Joker>(&EXAMPLE).unwrap(), 5905);
+        assert!(winnings::<Normal>(&[("2345", 1)]).is_err());
+
+        assert_eq!(Joker::substitute("KTJJT"), "KTTTT");
+        assert_eq!(Joker::substitute("JJJJJ"), "AAAAA");
+        // both pairs make a full house, the strongest is picked
+        assert_eq!(Joker::substitute("22J33"), "22333");
+        assert_eq!(Normal::substitute("KTJJT"), "KTJJT");
+
+        let report = report::<Joker>(&EXAMPLE).unwrap();
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines[1], "1\t32T3K\t-\tPair        \t765\t765");
+        assert_eq!(lines[5], "5\tKTJJT\tKTTTT\tFourOfAKind \t220\t1100");
+        assert_eq!(lines[6], "Total\t\t\t\t\t\t5905");
+        assert_eq!(lines[9], "FourOfAKind      3 ###");
+        assert_eq!(lines.len(), 15);
+    }
+}
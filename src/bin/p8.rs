@@ -1,4 +1,6 @@
-use std::{collections::HashMap, fs};
+use std::fs;
+
+use advent_of_code_2023::graph::{Graph, NodeId};
 
 fn main() {
     let content = fs::read_to_string("inputs/p8/map.txt").expect("reading the input");
@@ -7,29 +9,28 @@ fn main() {
     let instructions = lines.next().expect("reading instructions line");
     lines.next(); // skip empty line before list of nodes
 
-    let adjacency_list: HashMap<&str, (&str, &str)> = lines
-        .map(|line| {
-            let (start, end) = line.split_once('=').unwrap();
-            let parent = start.trim();
-            let (left, right) = end.split_once(',').unwrap();
-            let child_left = left.trim_matches(|c: char| !c.is_alphabetic());
-            let child_right = right.trim_matches(|c: char| !c.is_alphabetic());
-            (parent, (child_left, child_right))
-        })
-        .collect();
+    // every node has exactly two arcs, the first one is the left child, the second the right one
+    let mut network = Graph::new();
+    for line in lines {
+        let (start, end) = line.split_once('=').unwrap();
+        let parent = start.trim();
+        let (left, right) = end.split_once(',').unwrap();
+        network.add_arc(parent, left.trim_matches(|c: char| !c.is_alphanumeric()));
+        network.add_arc(parent, right.trim_matches(|c: char| !c.is_alphanumeric()));
+    }
+
+    let step = |node: NodeId, m: char| match m {
+        'L' => network.neighbours(node)[0],
+        'R' => network.neighbours(node)[1],
+        _ => panic!("invalid move {m}"),
+    };
 
-    let mut current = adjacency_list.get("AAA").expect("obtaining start node AAA");
+    let mut current = network.id("AAA").expect("obtaining start node AAA");
     // follow instructions for part 1
     for (steps, m) in instructions.chars().cycle().enumerate() {
-        let next = match m {
-            'L' => current.0,
-            'R' => current.1,
-            _ => panic!("invalid move {m}"),
-        };
+        current = step(current, m);
 
-        current = adjacency_list.get(next).expect("obtaining start node AAA");
-
-        if next == "ZZZ" {
+        if network.name(current) == "ZZZ" {
             println!("[PART 1] Found ZZZ in {} steps", steps + 1);
             break;
         }
@@ -40,27 +41,21 @@ fn main() {
     // This takes too long. Instead, I will collect the step # at which we reach a Z node for each
     // ghost, and stop once we have at least 3 each. Hopefully these events are cyclic and we can
     // determine how many cycles we need a lot faster than by simulating everything.
-    let mut ghosts: Vec<&str> = adjacency_list
-        .keys()
-        .filter(|node| node.ends_with('A'))
-        .copied()
+    let mut ghosts: Vec<NodeId> = network
+        .nodes()
+        .filter(|&node| network.name(node).ends_with('A'))
         .collect();
-    println!("Ghosts {ghosts:?}");
+    println!(
+        "Ghosts {:?}",
+        ghosts.iter().map(|&g| network.name(g)).collect::<Vec<_>>()
+    );
     let mut steps_to_z = vec![vec![]; ghosts.len()];
 
     for (steps, m) in instructions.chars().cycle().enumerate() {
         for (ghost, to_z) in ghosts.iter_mut().zip(steps_to_z.iter_mut()) {
-            let prev = *ghost;
-            let children = adjacency_list.get(prev).unwrap();
-            let next = match m {
-                'L' => children.0,
-                'R' => children.1,
-                _ => panic!("invalid move {m}"),
-            };
-
-            *ghost = next;
+            *ghost = step(*ghost, m);
 
-            if ghost.ends_with('Z') {
+            if network.name(*ghost).ends_with('Z') {
                 to_z.push(steps as u32 + 1);
             }
         }
@@ -0,0 +1,511 @@
+//! Client for the puzzle website: downloads inputs and submits answers.
+//!
+//! Everything is configured from the environment, so that tests (or a mirror) can point it
+//! somewhere else than the real site:
+//! - `AOC_SESSION`: value of the `session` cookie of a logged in browser (required)
+//! - `AOC_BASE_URL`: defaults to `https://adventofcode.com`
+//! - `AOC_YEAR`: defaults to 2023
+//!
+//! Inputs are cached in `inputs/pXX/`, with the file name that day reads. Submissions and the
+//! server's verdicts are recorded in `inputs/pXX/answers.txt`, and answers known to be wrong (the
+//! same answer, or one beyond a "too high"/"too low" bound) are refused before reaching the
+//! server. Requests are spaced at least [`Config::min_interval`] apart, across runs too.
+
+use std::{
+    env, fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use thiserror::Error;
+
+const USER_AGENT: &str = "github.com/AxelMontini/advent-of-code-2023";
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("AOC_SESSION is not set, copy it from the `session` cookie of a logged in browser")]
+    MissingSession,
+    #[error("invalid AOC_YEAR {0:?}")]
+    InvalidYear(String),
+    #[error("request failed")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("accessing the input cache")]
+    Io(#[from] io::Error),
+    #[error("{answer} was already submitted for part {part}, and it was {verdict}")]
+    AlreadySubmitted {
+        part: u8,
+        answer: String,
+        verdict: Verdict,
+    },
+    #[error("{answer} cannot be right, {bound} was already {verdict}")]
+    OutOfBounds {
+        answer: String,
+        bound: String,
+        verdict: Verdict,
+    },
+    #[error("part {0} is already solved")]
+    AlreadySolved(u8),
+    #[error("cannot understand the server's reply: {0:?}")]
+    UnexpectedResponse(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub base_url: String,
+    pub year: u16,
+    pub session: String,
+    /// Where inputs and answers are kept, `inputs` by default
+    pub cache_dir: PathBuf,
+    pub min_interval: Duration,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, ClientError> {
+        let session = env::var("AOC_SESSION").map_err(|_| ClientError::MissingSession)?;
+        let base_url =
+            env::var("AOC_BASE_URL").unwrap_or_else(|_| "https://adventofcode.com".to_string());
+        let year = match env::var("AOC_YEAR") {
+            Ok(year) => year.parse().map_err(|_| ClientError::InvalidYear(year))?,
+            Err(_) => 2023,
+        };
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            year,
+            session,
+            cache_dir: "inputs".into(),
+            min_interval: Duration::from_secs(5),
+        })
+    }
+}
+
+/// What the server thinks of an answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    TooHigh,
+    TooLow,
+    /// Wrong, without hints
+    Wrong,
+    /// Rate limited by the server, the answer was not checked
+    TooRecent,
+    /// The part was already solved, the answer was not checked
+    AlreadySolved,
+}
+
+impl Verdict {
+    /// Finds the verdict in the HTML page returned after submitting
+    fn from_response(html: &str) -> Option<Self> {
+        Some(match () {
+            _ if html.contains("That's the right answer") => Self::Correct,
+            _ if html.contains("your answer is too high") => Self::TooHigh,
+            _ if html.contains("your answer is too low") => Self::TooLow,
+            _ if html.contains("That's not the right answer") => Self::Wrong,
+            _ if html.contains("You gave an answer too recently") => Self::TooRecent,
+            _ if html.contains("You don't seem to be solving the right level") => {
+                Self::AlreadySolved
+            }
+            _ => return None,
+        })
+    }
+
+    /// Name used in the answers log
+    fn key(self) -> &'static str {
+        match self {
+            Self::Correct => "correct",
+            Self::TooHigh => "too-high",
+            Self::TooLow => "too-low",
+            Self::Wrong => "wrong",
+            Self::TooRecent => "too-recent",
+            Self::AlreadySolved => "already-solved",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        [
+            Self::Correct,
+            Self::TooHigh,
+            Self::TooLow,
+            Self::Wrong,
+            Self::TooRecent,
+            Self::AlreadySolved,
+        ]
+        .into_iter()
+        .find(|v| v.key() == key)
+    }
+
+    /// Whether the server actually checked the answer, and the verdict is worth remembering
+    fn is_final(self) -> bool {
+        !matches!(self, Self::TooRecent | Self::AlreadySolved)
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Correct => "correct",
+            Self::TooHigh => "too high",
+            Self::TooLow => "too low",
+            Self::Wrong => "wrong",
+            Self::TooRecent => "submitted too recently, wait a bit",
+            Self::AlreadySolved => "not checked, the part is already solved",
+        })
+    }
+}
+
+/// A line of the answers log
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Submission {
+    part: u8,
+    answer: String,
+    verdict: Verdict,
+}
+
+/// Input file name of each day, as read by its binary
+pub fn input_file(day: u8) -> &'static str {
+    match day {
+        1 => "calibration.txt",
+        2 => "games.txt",
+        3 => "schematic.txt",
+        4 => "scratchcards.txt",
+        5 => "almanac.txt",
+        6 => "races.txt",
+        7 => "hands.txt",
+        8 | 17 => "map.txt",
+        9 => "data.txt",
+        10 => "pipes.txt",
+        11 => "picture.txt",
+        12 => "springs.txt",
+        13 => "reflections.txt",
+        14 => "stones.txt",
+        16 => "tiles.txt",
+        25 => "wiring.txt",
+        _ => "input.txt",
+    }
+}
+
+pub struct Client {
+    config: Config,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    pub fn new(config: Config) -> Self {
+        let agent = ureq::AgentBuilder::new().user_agent(USER_AGENT).build();
+        Self { config, agent }
+    }
+
+    pub fn input_path(&self, day: u8) -> PathBuf {
+        self.config
+            .cache_dir
+            .join(format!("p{day}"))
+            .join(input_file(day))
+    }
+
+    fn answers_path(&self, day: u8) -> PathBuf {
+        self.config
+            .cache_dir
+            .join(format!("p{day}"))
+            .join("answers.txt")
+    }
+
+    fn url(&self, day: u8, page: &str) -> String {
+        format!(
+            "{}/{}/day/{day}/{page}",
+            self.config.base_url, self.config.year
+        )
+    }
+
+    fn cookie(&self) -> String {
+        format!("session={}", self.config.session)
+    }
+
+    /// Downloads the input of the day, unless it is already cached. Returns where it is.
+    pub fn fetch_input(&self, day: u8) -> Result<PathBuf, ClientError> {
+        let path = self.input_path(day);
+        if path.exists() {
+            return Ok(path);
+        }
+
+        self.throttle()?;
+        let input = self
+            .agent
+            .get(&self.url(day, "input"))
+            .set("Cookie", &self.cookie())
+            .call()
+            .map_err(Box::new)?
+            .into_string()?;
+
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, input)?;
+        Ok(path)
+    }
+
+    /// Submits the answer to a part, unless the log already tells it's wrong.
+    pub fn submit(&self, day: u8, part: u8, answer: &str) -> Result<Verdict, ClientError> {
+        let answer = answer.trim();
+        let log = self.read_answers(day)?;
+        check_answer(&log, part, answer)?;
+
+        self.throttle()?;
+        let html = self
+            .agent
+            .post(&self.url(day, "answer"))
+            .set("Cookie", &self.cookie())
+            .send_form(&[("level", &part.to_string()), ("answer", answer)])
+            .map_err(Box::new)?
+            .into_string()?;
+        let verdict = Verdict::from_response(&html)
+            .ok_or_else(|| ClientError::UnexpectedResponse(html.chars().take(300).collect()))?;
+
+        if verdict.is_final() {
+            let path = self.answers_path(day);
+            fs::create_dir_all(path.parent().unwrap())?;
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            writeln!(file, "{part}\t{answer}\t{}", verdict.key())?;
+        }
+        Ok(verdict)
+    }
+
+    fn read_answers(&self, day: u8) -> Result<Vec<Submission>, ClientError> {
+        let content = match fs::read_to_string(self.answers_path(day)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                Some(Submission {
+                    part: fields.next()?.parse().ok()?,
+                    answer: fields.next()?.to_string(),
+                    verdict: Verdict::from_key(fields.next()?)?,
+                })
+            })
+            .collect())
+    }
+
+    /// Waits until `min_interval` has passed since the last request, then marks a new one. The
+    /// time of the last request (milliseconds since the epoch) is kept in a file in the cache, so
+    /// that it holds across runs.
+    fn throttle(&self) -> Result<(), ClientError> {
+        let stamp = self.config.cache_dir.join(".last-request");
+        let now = || SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let last = fs::read_to_string(&stamp)
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .map(Duration::from_millis);
+        if let Some(wait) =
+            last.and_then(|last| (last + self.config.min_interval).checked_sub(now()))
+        {
+            thread::sleep(wait);
+        }
+        fs::create_dir_all(&self.config.cache_dir)?;
+        fs::write(stamp, now().as_millis().to_string())?;
+        Ok(())
+    }
+}
+
+/// Refuses answers that the log proves wrong: already submitted, or past a known bound.
+fn check_answer(log: &[Submission], part: u8, answer: &str) -> Result<(), ClientError> {
+    let mut previous = log.iter().filter(|s| s.part == part);
+    if previous.clone().any(|s| s.verdict == Verdict::Correct) {
+        return Err(ClientError::AlreadySolved(part));
+    }
+    if let Some(s) = previous.clone().find(|s| s.answer == answer) {
+        return Err(ClientError::AlreadySubmitted {
+            part,
+            answer: answer.to_string(),
+            verdict: s.verdict,
+        });
+    }
+
+    let Ok(value) = answer.parse::<i128>() else {
+        return Ok(());
+    };
+    let beyond = |s: &Submission| match (s.verdict, s.answer.parse::<i128>()) {
+        (Verdict::TooHigh, Ok(bound)) => value >= bound,
+        (Verdict::TooLow, Ok(bound)) => value <= bound,
+        _ => false,
+    };
+    match previous.find(|s| beyond(s)) {
+        Some(s) => Err(ClientError::OutOfBounds {
+            answer: answer.to_string(),
+            bound: s.answer.clone(),
+            verdict: s.verdict,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{Client, ClientError, Config, Verdict};
+
+    /// Local HTTP server answering every request with `reply(request)`, where the request is
+    /// the request line, headers and body. Requests are kept for inspection.
+    fn mock_server(reply: fn(&str) -> String) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let log = requests.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&mut stream);
+                let mut request = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some((k, v)) = line.split_once(':') {
+                        if k.eq_ignore_ascii_case("content-length") {
+                            content_length = v.trim().parse().unwrap();
+                        }
+                    }
+                    request += &line;
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                request += &String::from_utf8(body).unwrap();
+
+                let body = reply(&request);
+                log.lock().unwrap().push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn client(url: String, name: &str) -> (Client, PathBuf) {
+        let cache_dir =
+            std::env::temp_dir().join(format!("aoc-client-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&cache_dir);
+        let config = Config {
+            base_url: url,
+            year: 2023,
+            session: "s3cr3t".to_string(),
+            cache_dir: cache_dir.clone(),
+            min_interval: Duration::ZERO,
+        };
+        (Client::new(config), cache_dir)
+    }
+
+    #[test]
+    fn fetch_is_cached() {
+        let (url, requests) = mock_server(|_| "1abc2\npqr3stu8vwx\n".to_string());
+        let (client, cache_dir) = client(url, "fetch");
+
+        let path = client.fetch_input(1).unwrap();
+        assert_eq!(path, cache_dir.join("p1/calibration.txt"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1abc2\npqr3stu8vwx\n");
+        assert_eq!(client.fetch_input(1).unwrap(), path);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /2023/day/1/input HTTP/1.1"));
+        assert!(requests[0].contains("session=s3cr3t"));
+    }
+
+    #[test]
+    fn submissions_are_remembered() {
+        let (url, requests) = mock_server(|request| {
+            let answer = request.rsplit("answer=").next().unwrap();
+            match answer.parse::<u32>().unwrap() {
+                42 => "<article><p>That's the right answer!</p></article>",
+                a if a > 42 => "<article><p>That's not the right answer; your answer is too high.</p></article>",
+                _ => "<article><p>That's not the right answer; your answer is too low.</p></article>",
+            }
+            .to_string()
+        });
+        let (client, cache_dir) = client(url, "submit");
+
+        assert_eq!(client.submit(5, 1, "100").unwrap(), Verdict::TooHigh);
+        assert_eq!(client.submit(5, 1, "7\n").unwrap(), Verdict::TooLow);
+        assert!(matches!(
+            client.submit(5, 1, "100"),
+            Err(ClientError::AlreadySubmitted {
+                verdict: Verdict::TooHigh,
+                ..
+            })
+        ));
+        assert!(matches!(
+            client.submit(5, 1, "150"),
+            Err(ClientError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            client.submit(5, 1, "3"),
+            Err(ClientError::OutOfBounds { .. })
+        ));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        assert_eq!(client.submit(5, 1, "42").unwrap(), Verdict::Correct);
+        assert!(matches!(
+            client.submit(5, 1, "43"),
+            Err(ClientError::AlreadySolved(1))
+        ));
+        // part 2 has its own history
+        assert_eq!(client.submit(5, 2, "100").unwrap(), Verdict::TooHigh);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[0].starts_with("POST /2023/day/5/answer HTTP/1.1"));
+        assert!(requests[0].ends_with("level=1&answer=100"));
+        assert_eq!(
+            fs::read_to_string(cache_dir.join("p5/answers.txt")).unwrap(),
+            "1\t100\ttoo-high\n1\t7\ttoo-low\n1\t42\tcorrect\n2\t100\ttoo-high\n"
+        );
+    }
+
+    #[test]
+    fn requests_are_throttled() {
+        let (url, requests) = mock_server(|_| "input".to_string());
+        let (mut client, _) = client(url, "throttle");
+        client.config.min_interval = Duration::from_millis(300);
+
+        let start = Instant::now();
+        client.fetch_input(2).unwrap();
+        client.fetch_input(3).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn verdicts() {
+        assert_eq!(
+            Verdict::from_response("You gave an answer too recently; you have 30s left to wait."),
+            Some(Verdict::TooRecent)
+        );
+        assert_eq!(
+            Verdict::from_response("That's not the right answer. If you're stuck, ..."),
+            Some(Verdict::Wrong)
+        );
+        assert_eq!(Verdict::from_response("<html></html>"), None);
+    }
+}
@@ -0,0 +1,116 @@
+//! Interactive REPL to poke at a parsed puzzle input, instead of sprinkling `println!`s around.
+//!
+//! A day parses its input once, wraps the result in a type implementing [`Explore`] and hands it
+//! to [`repl`] when run as `cargo run --bin pXX -- explore`. Every line typed is a command name
+//! followed by its arguments; `help` lists the commands and `quit` (or EOF) leaves.
+
+use std::io::{self, BufRead, Write};
+
+/// A command understood by an [`Explore`] implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Command {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+}
+
+/// Implemented by each day to register its commands.
+pub trait Explore {
+    const COMMANDS: &'static [Command];
+
+    /// Runs the command `name` (one of [`Explore::COMMANDS`]) with the rest of the line as
+    /// arguments. Returns what to print.
+    fn run(&mut self, name: &str, args: &str) -> anyhow::Result<String>;
+}
+
+/// Whether the program was started as `pXX explore`
+pub fn requested() -> bool {
+    std::env::args().nth(1).as_deref() == Some("explore")
+}
+
+/// Runs the REPL on stdin/stdout until `quit` or end of input.
+pub fn repl(day: &str, explorer: &mut impl Explore) -> io::Result<()> {
+    run_session(day, explorer, io::stdin().lock(), io::stdout().lock())
+}
+
+fn run_session<E: Explore>(
+    day: &str,
+    explorer: &mut E,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    writeln!(
+        output,
+        "Exploring {day}, type `help` for the list of commands"
+    )?;
+    let mut lines = input.lines();
+    loop {
+        write!(output, "{day}> ")?;
+        output.flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            writeln!(output)?;
+            return Ok(());
+        };
+        let (name, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+
+        match name {
+            "" => (),
+            "quit" | "exit" => return Ok(()),
+            "help" => {
+                for c in E::COMMANDS {
+                    writeln!(output, "  {:<28} {}", c.usage, c.help)?;
+                }
+                writeln!(output, "  {:<28} leave", "quit")?;
+            }
+            _ if E::COMMANDS.iter().any(|c| c.name == name) => {
+                match explorer.run(name, args.trim()) {
+                    Ok(out) => writeln!(output, "{}", out.trim_end())?,
+                    Err(e) => writeln!(output, "error: {e:#}")?,
+                }
+            }
+            _ => writeln!(output, "unknown command {name:?}, try `help`")?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_session, Command, Explore};
+
+    struct Counter(u32);
+
+    impl Explore for Counter {
+        const COMMANDS: &'static [Command] = &[Command {
+            name: "add",
+            usage: "add <n>",
+            help: "adds n to the counter",
+        }];
+
+        fn run(&mut self, _name: &str, args: &str) -> anyhow::Result<String> {
+            self.0 += args.parse::<u32>()?;
+            Ok(self.0.to_string())
+        }
+    }
+
+    #[test]
+    fn session() {
+        let input = "add 2\n\nadd x\nsub 1\nadd 3\nquit\nadd 100\n";
+        let mut output = vec![];
+        let mut counter = Counter(0);
+        run_session("p0", &mut counter, input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(counter.0, 5);
+        let output = String::from_utf8(output).unwrap();
+        let replies: Vec<_> = output.lines().skip(1).collect();
+        assert_eq!(
+            replies,
+            [
+                "p0> 2",
+                "p0> p0> error: invalid digit found in string",
+                "p0> unknown command \"sub\", try `help`",
+                "p0> 5",
+                "p0> ",
+            ]
+        );
+    }
+}
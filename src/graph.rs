@@ -0,0 +1,212 @@
+//! Graph of named nodes, shared by the days whose input is a network (p8, p25).
+//!
+//! Nodes are interned by name and referred to by a dense [`NodeId`], so that the algorithms can
+//! use plain vectors instead of hashing strings all the time. Arcs keep their insertion order,
+//! which p8 relies on to tell the left child from the right one.
+
+use std::collections::{BinaryHeap, HashMap};
+
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, Default)]
+pub struct Graph<'s> {
+    names: Vec<&'s str>,
+    ids: HashMap<&'s str, NodeId>,
+    adjacency: Vec<Vec<NodeId>>,
+}
+
+/// Result of [`Graph::min_cut`]: the amount of edges crossing the cut and the nodes on one side
+/// of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cut {
+    pub weight: u32,
+    pub side: Vec<NodeId>,
+}
+
+impl<'s> Graph<'s> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of the node with the given name, adding it if not present yet.
+    pub fn add_node(&mut self, name: &'s str) -> NodeId {
+        *self.ids.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            self.adjacency.push(vec![]);
+            self.names.len() - 1
+        })
+    }
+
+    /// Adds a directed arc `from -> to`, creating the nodes if needed.
+    pub fn add_arc(&mut self, from: &'s str, to: &'s str) {
+        let (from, to) = (self.add_node(from), self.add_node(to));
+        self.adjacency[from].push(to);
+    }
+
+    /// Adds an undirected edge, i.e. an arc in both directions.
+    pub fn add_edge(&mut self, a: &'s str, b: &'s str) {
+        let (a, b) = (self.add_node(a), self.add_node(b));
+        self.adjacency[a].push(b);
+        self.adjacency[b].push(a);
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &'s str {
+        self.names[id]
+    }
+
+    /// Successors of `id`, in the order the arcs were added.
+    pub fn neighbours(&self, id: NodeId) -> &[NodeId] {
+        &self.adjacency[id]
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        0..self.names.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Global minimum cut of an undirected graph (every edge has weight 1), using Stoer–Wagner.
+    /// Returns `None` if the graph has less than two nodes, as there is nothing to cut.
+    ///
+    /// Each phase grows a set from an arbitrary node, always adding the node most tightly
+    /// connected to it. The last node added (`t`) is separated from the rest by a "cut of the
+    /// phase", then it is merged with the second-last (`s`). The best cut of all phases is the
+    /// minimum cut. Merged nodes keep their neighbours in a map, so parallel edges just add up.
+    pub fn min_cut(&self) -> Option<Cut> {
+        if self.len() < 2 {
+            return None;
+        }
+
+        let mut weights: Vec<HashMap<NodeId, u32>> = vec![HashMap::new(); self.len()];
+        for a in self.nodes() {
+            for &b in self.neighbours(a) {
+                if a != b {
+                    *weights[a].entry(b).or_default() += 1;
+                }
+            }
+        }
+
+        let mut members: Vec<Vec<NodeId>> = self.nodes().map(|n| vec![n]).collect();
+        let mut active: Vec<NodeId> = self.nodes().collect();
+        let mut best: Option<Cut> = None;
+
+        while active.len() > 1 {
+            let mut key = vec![0u32; self.len()];
+            let mut added = vec![false; self.len()];
+            // lazy deletion: stale entries are skipped when popped
+            let mut heap: BinaryHeap<(u32, NodeId)> = active.iter().map(|&n| (0, n)).collect();
+            let (mut s, mut t) = (active[0], active[0]);
+
+            while let Some((k, n)) = heap.pop() {
+                if added[n] || k != key[n] {
+                    continue;
+                }
+                added[n] = true;
+                (s, t) = (t, n);
+                for (&m, &w) in &weights[n] {
+                    if !added[m] {
+                        key[m] += w;
+                        heap.push((key[m], m));
+                    }
+                }
+            }
+
+            if best.as_ref().is_none_or(|b| key[t] < b.weight) {
+                best = Some(Cut {
+                    weight: key[t],
+                    side: members[t].clone(),
+                });
+            }
+
+            // merge t into s
+            let t_weights = std::mem::take(&mut weights[t]);
+            for (m, w) in t_weights {
+                weights[m].remove(&t);
+                if m != s {
+                    *weights[s].entry(m).or_default() += w;
+                    *weights[m].entry(s).or_default() += w;
+                }
+            }
+            let t_members = std::mem::take(&mut members[t]);
+            members[s].extend(t_members);
+            active.retain(|&n| n != t);
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Graph;
+
+    #[test]
+    fn arcs_keep_order() {
+        let mut g = Graph::new();
+        g.add_arc("AAA", "BBB");
+        g.add_arc("AAA", "CCC");
+        g.add_arc("BBB", "BBB");
+        g.add_arc("BBB", "BBB");
+
+        let aaa = g.id("AAA").unwrap();
+        let bbb = g.id("BBB").unwrap();
+        let names: Vec<_> = g.neighbours(aaa).iter().map(|&n| g.name(n)).collect();
+        assert_eq!(names, ["BBB", "CCC"]);
+        assert_eq!(g.neighbours(bbb), [bbb, bbb]);
+        assert_eq!(g.len(), 3);
+        assert_eq!(g.id("ZZZ"), None);
+    }
+
+    #[test]
+    fn min_cut_example() {
+        let input = "jqt: rhn xhk nvd
+rsh: frs pzl lsr
+xhk: hfx
+cmg: qnr nvd lhk bvb
+rhn: xhk bvb hfx
+bvb: xhk hfx
+pzl: lsr hfx nvd
+qnr: nvd
+ntq: jqt hfx bvb xhk
+nvd: lhk
+lsr: lhk
+rzs: qnr cmg lsr rsh
+frs: qnr lhk lsr";
+
+        let mut g = Graph::new();
+        for line in input.lines() {
+            let (a, rest) = line.split_once(':').unwrap();
+            for b in rest.split_whitespace() {
+                g.add_edge(a, b);
+            }
+        }
+
+        let cut = g.min_cut().unwrap();
+        assert_eq!(cut.weight, 3);
+        let mut sizes = [cut.side.len(), g.len() - cut.side.len()];
+        sizes.sort();
+        assert_eq!(sizes, [6, 9]);
+    }
+
+    #[test]
+    fn min_cut_disconnected() {
+        let mut g = Graph::new();
+        g.add_edge("a", "b");
+        g.add_edge("c", "d");
+        assert_eq!(g.min_cut().unwrap().weight, 0);
+
+        let mut g = Graph::new();
+        g.add_node("a");
+        assert_eq!(g.min_cut(), None);
+    }
+}
@@ -0,0 +1,7 @@
+//! Code shared between the daily binaries in `src/bin`.
+
+pub mod client;
+pub mod explore;
+pub mod graph;
+pub mod playback;
+pub mod render;
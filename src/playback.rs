@@ -0,0 +1,199 @@
+//! Step-by-step playback of simulations.
+//!
+//! A day wraps its simulation in a type implementing [`Step`], which advances it by one step and
+//! draws its current state as a [`Frame`]. The frames are recorded and then replayed in the
+//! terminal, or exported as an animated GIF (needs the `plotters` feature). Days get a `--play`
+//! option through [`Playback::from_args`].
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+use crate::render::{Frame, RenderError};
+
+/// Hook implemented by simulations that can be played back.
+pub trait Step {
+    /// Advances the simulation by one step. Returns `false` once there is nothing left to do.
+    fn step(&mut self) -> bool;
+
+    /// Draws the current state.
+    fn frame(&self) -> Frame;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Terminal,
+    /// One GIF per simulation, in the given directory
+    Gif(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playback {
+    pub output: Output,
+    /// Frames per second
+    pub fps: u32,
+    /// Maximum amount of steps to record
+    pub steps: usize,
+}
+
+impl Playback {
+    /// Reads `--play[=gif[:dir]]`, `--fps=N` (default 10) and `--steps=N` (default 1000) from
+    /// the program arguments. GIFs go to `inputs/<day>` unless a directory is given.
+    /// Returns `None` without `--play`.
+    pub fn from_args(day: &str) -> Result<Option<Self>, RenderError> {
+        Self::parse(std::env::args().skip(1), day)
+    }
+
+    fn parse(args: impl Iterator<Item = String>, day: &str) -> Result<Option<Self>, RenderError> {
+        let mut output = None;
+        let mut fps = 10;
+        let mut steps = 1000;
+        for arg in args {
+            let (key, value) = arg.split_once('=').unwrap_or((&arg, ""));
+            let invalid = || RenderError::InvalidPlayback(arg.clone());
+            match (key, value.split_once(':').unwrap_or((value, ""))) {
+                ("--play", ("", "")) => output = Some(Output::Terminal),
+                ("--play", ("gif", "")) => {
+                    output = Some(Output::Gif(PathBuf::from("inputs").join(day)))
+                }
+                ("--play", ("gif", dir)) => output = Some(Output::Gif(dir.into())),
+                ("--play", _) => return Err(invalid()),
+                ("--fps", _) => fps = value.parse().ok().filter(|&f| f > 0).ok_or_else(invalid)?,
+                ("--steps", _) => steps = value.parse().map_err(|_| invalid())?,
+                _ => (),
+            }
+        }
+        Ok(output.map(|output| Self { output, fps, steps }))
+    }
+
+    /// Records the simulation and plays it back. `name` identifies the simulation among the
+    /// ones of the same day, and is used as file name for GIFs.
+    pub fn run(&self, sim: &mut impl Step, name: &str) -> Result<(), RenderError> {
+        let recording = Recording::record(sim, self.steps);
+        match &self.output {
+            Output::Terminal => recording.play(self.fps),
+            Output::Gif(dir) => {
+                std::fs::create_dir_all(dir)?;
+                recording.gif(&dir.join(format!("{name}.gif")), self.fps)
+            }
+        }
+    }
+}
+
+/// Frames of a simulation, the first one being the initial state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    frames: Vec<Frame>,
+}
+
+impl Recording {
+    /// Records the initial state and the state after every step, until the simulation is over
+    /// or `max_steps` steps have been taken.
+    pub fn record(sim: &mut impl Step, max_steps: usize) -> Self {
+        let mut frames = vec![sim.frame()];
+        for _ in 0..max_steps {
+            if !sim.step() {
+                break;
+            }
+            frames.push(sim.frame());
+        }
+        Self { frames }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Replays the frames in the terminal, redrawing the screen each time.
+    pub fn play(&self, fps: u32) -> Result<(), RenderError> {
+        let delay = Duration::from_secs(1) / fps;
+        let mut stdout = io::stdout().lock();
+        for (i, frame) in self.frames.iter().enumerate() {
+            // clear screen and move cursor to the top-left corner
+            write!(stdout, "\x1b[2J\x1b[H")?;
+            writeln!(stdout, "step {i}/{}", self.frames.len() - 1)?;
+            stdout.write_all(frame.text(true).as_bytes())?;
+            stdout.flush()?;
+            thread::sleep(delay);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "plotters"))]
+    pub fn gif(&self, _path: &std::path::Path, _fps: u32) -> Result<(), RenderError> {
+        Err(RenderError::NoPlotters)
+    }
+
+    /// Exports the frames as an animated GIF. Frames are drawn as in [`Frame::render`], and all
+    /// of them must have the same size.
+    #[cfg(feature = "plotters")]
+    pub fn gif(&self, path: &std::path::Path, fps: u32) -> Result<(), RenderError> {
+        use plotters::prelude::*;
+
+        let size = self.frames[0].pixels();
+        let root = BitMapBackend::gif(path, size, 1000 / fps)
+            .map_err(|e| RenderError::Plot(e.to_string()))?
+            .into_drawing_area();
+        for frame in &self.frames {
+            assert_eq!(frame.pixels(), size, "all frames must have the same size");
+            frame.draw(&root)?;
+            root.present().map_err(crate::render::plot_err)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Output, Playback, Recording, Step};
+    use crate::render::Frame;
+
+    /// Moves a `#` one cell to the right, until the end of the line
+    struct Slider(usize);
+
+    impl Step for Slider {
+        fn step(&mut self) -> bool {
+            self.0 += 1;
+            self.0 < 3
+        }
+
+        fn frame(&self) -> Frame {
+            Frame::new(3, 1, |x, _| if x == self.0 { '#' } else { '.' })
+        }
+    }
+
+    #[test]
+    fn record_until_done() {
+        let text = |r: &Recording| r.frames().iter().map(|f| f.text(false)).collect::<Vec<_>>();
+
+        let recording = Recording::record(&mut Slider(0), 100);
+        assert_eq!(text(&recording), ["#..\n", ".#.\n", "..#\n"]);
+
+        let recording = Recording::record(&mut Slider(0), 1);
+        assert_eq!(text(&recording), ["#..\n", ".#.\n"]);
+    }
+
+    #[test]
+    fn parse_args() {
+        let parse = |args: &[&str]| Playback::parse(args.iter().map(|s| s.to_string()), "p1");
+
+        assert_eq!(parse(&["--render"]).unwrap(), None);
+        assert_eq!(
+            parse(&["--play", "--fps=2"]).unwrap(),
+            Some(Playback {
+                output: Output::Terminal,
+                fps: 2,
+                steps: 1000
+            })
+        );
+        assert_eq!(
+            parse(&["--steps=5", "--play=gif"]).unwrap().unwrap().output,
+            Output::Gif("inputs/p1".into())
+        );
+        assert!(parse(&["--play=mp4"]).is_err());
+        assert!(parse(&["--play", "--fps=0"]).is_err());
+    }
+}
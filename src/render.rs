@@ -0,0 +1,409 @@
+//! Rendering of grid puzzles, to the terminal or (with the `plotters` feature) to PNG.
+//!
+//! A day builds a [`Frame`] out of its grid, styling the cells as it likes, and then adds
+//! overlays on top: highlighted cells (only change the background) and paths (drawn as arrows
+//! from one cell to the next). Days get a `--render` option through [`Target::from_args`].
+
+use std::{
+    fmt::Write as _,
+    io::{self, Write as _},
+    path::PathBuf,
+};
+
+pub use colored::Color;
+use colored::Colorize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error("unknown render target {0:?}, expected `ansi`, `plain` or `png[:dir]`")]
+    UnknownTarget(String),
+    #[error(
+        "invalid playback argument {0:?}, expected `--play[=gif[:dir]]`, `--fps=N` or `--steps=N`"
+    )]
+    InvalidPlayback(String),
+    #[error("image output needs the `plotters` feature")]
+    NoPlotters,
+    #[error("writing rendered output")]
+    Io(#[from] io::Error),
+    #[error("drawing image: {0}")]
+    Plot(String),
+}
+
+/// Where a frame goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// Coloured text on stdout
+    Ansi,
+    /// Text on stdout, colours are dropped but paths are still drawn
+    Plain,
+    /// One PNG per frame, in the given directory
+    Png(PathBuf),
+}
+
+impl Target {
+    /// Reads `--render[=ansi|plain|png[:dir]]` from the program arguments. PNGs go to
+    /// `inputs/<day>` unless a directory is given.
+    pub fn from_args(day: &str) -> Result<Option<Self>, RenderError> {
+        std::env::args()
+            .skip(1)
+            .find_map(|arg| {
+                arg.strip_prefix("--render")
+                    .filter(|rest| rest.is_empty() || rest.starts_with('='))
+                    .map(|rest| Self::parse(rest.trim_start_matches('='), day))
+            })
+            .transpose()
+    }
+
+    fn parse(s: &str, day: &str) -> Result<Self, RenderError> {
+        match s.split_once(':').unwrap_or((s, "")) {
+            ("" | "ansi", "") => Ok(Target::Ansi),
+            ("plain", "") => Ok(Target::Plain),
+            ("png", "") => Ok(Target::Png(PathBuf::from("inputs").join(day))),
+            ("png", dir) => Ok(Target::Png(dir.into())),
+            _ => Err(RenderError::UnknownTarget(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+impl Style {
+    pub fn fg(color: Color) -> Self {
+        Self {
+            fg: Some(color),
+            bg: None,
+        }
+    }
+
+    pub fn on(self, bg: Color) -> Self {
+        Self {
+            bg: Some(bg),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Overlay {
+    Highlight(Vec<(usize, usize)>, Color),
+    Path(Vec<(usize, usize)>, Color),
+}
+
+/// A grid of styled characters plus overlays. Positions are `(x, y)` from the top-left corner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<(char, Style)>,
+    overlays: Vec<Overlay>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize, mut cell: impl FnMut(usize, usize) -> char) -> Self {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| (cell(x, y), Style::default()))
+            .collect();
+        Self {
+            width,
+            height,
+            cells,
+            overlays: vec![],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Assigns a style to every cell, given its position and character.
+    pub fn style_with(&mut self, mut style: impl FnMut(usize, usize, char) -> Style) {
+        let width = self.width;
+        for (i, (c, s)) in self.cells.iter_mut().enumerate() {
+            *s = style(i % width, i / width, *c);
+        }
+    }
+
+    /// Sets the background of the given cells.
+    pub fn highlight(&mut self, cells: impl IntoIterator<Item = (usize, usize)>, color: Color) {
+        self.overlays
+            .push(Overlay::Highlight(cells.into_iter().collect(), color));
+    }
+
+    /// Draws a path through the given cells, in order. Each cell points to the next one.
+    pub fn path(&mut self, cells: impl IntoIterator<Item = (usize, usize)>, color: Color) {
+        self.overlays
+            .push(Overlay::Path(cells.into_iter().collect(), color));
+    }
+
+    /// Cells with the overlays applied. Overlays added later are on top.
+    fn flatten(&self) -> Vec<(char, Style)> {
+        let mut cells = self.cells.clone();
+        let idx = |(x, y): (usize, usize)| x + y * self.width;
+        for overlay in &self.overlays {
+            match overlay {
+                Overlay::Highlight(positions, color) => positions
+                    .iter()
+                    .for_each(|&p| cells[idx(p)].1.bg = Some(*color)),
+                Overlay::Path(positions, color) => {
+                    for (i, &p) in positions.iter().enumerate() {
+                        if let Some(&next) = positions.get(i + 1) {
+                            cells[idx(p)].0 = arrow(p, next);
+                        }
+                        cells[idx(p)].1.fg = Some(*color);
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    /// The frame as text, one line per row. With `colored`, styles become ANSI escapes.
+    pub fn text(&self, colored: bool) -> String {
+        let mut out = String::with_capacity(self.cells.len() + self.height);
+        for row in self.flatten().chunks(self.width) {
+            for &(c, style) in row {
+                if colored {
+                    let mut s = c.to_string().normal();
+                    if let Some(fg) = style.fg {
+                        s = s.color(fg);
+                    }
+                    if let Some(bg) = style.bg {
+                        s = s.on_color(bg);
+                    }
+                    write!(out, "{s}").unwrap();
+                } else {
+                    out.push(c);
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the frame to the target. `name` identifies the frame among the ones of the same
+    /// day, and is used as file name for images.
+    pub fn render(&self, target: &Target, name: &str) -> Result<(), RenderError> {
+        match target {
+            Target::Ansi | Target::Plain => {
+                let mut stdout = io::stdout().lock();
+                writeln!(stdout, "{name}:")?;
+                stdout.write_all(self.text(*target == Target::Ansi).as_bytes())?;
+                Ok(())
+            }
+            Target::Png(dir) => {
+                std::fs::create_dir_all(dir)?;
+                self.png(&dir.join(format!("{name}.png")))
+            }
+        }
+    }
+
+    #[cfg(not(feature = "plotters"))]
+    fn png(&self, _path: &std::path::Path) -> Result<(), RenderError> {
+        Err(RenderError::NoPlotters)
+    }
+
+    #[cfg(feature = "plotters")]
+    fn png(&self, path: &std::path::Path) -> Result<(), RenderError> {
+        use plotters::prelude::*;
+
+        let root = BitMapBackend::new(path, self.pixels()).into_drawing_area();
+        self.draw(&root)?;
+        root.present().map_err(plot_err)
+    }
+
+    /// Size of the frame when drawn as an image
+    #[cfg(feature = "plotters")]
+    pub(crate) fn pixels(&self) -> (u32, u32) {
+        (
+            self.width as u32 * CELL_PX as u32,
+            self.height as u32 * CELL_PX as u32,
+        )
+    }
+
+    /// Every cell is a square: background colour, and a smaller square in the foreground colour
+    /// unless the cell is empty (`.` or space). Paths are drawn as lines between cell centres.
+    #[cfg(feature = "plotters")]
+    pub(crate) fn draw<DB: plotters::prelude::DrawingBackend>(
+        &self,
+        root: &plotters::prelude::DrawingArea<DB, plotters::coord::Shift>,
+    ) -> Result<(), RenderError> {
+        use plotters::prelude::*;
+
+        const CELL: i32 = CELL_PX;
+        root.fill(&WHITE).map_err(plot_err)?;
+
+        let corner = |x: usize, y: usize| (x as i32 * CELL, y as i32 * CELL);
+        let centre =
+            |(x, y): (usize, usize)| (x as i32 * CELL + CELL / 2, y as i32 * CELL + CELL / 2);
+
+        for (i, (c, style)) in self.flatten().into_iter().enumerate() {
+            let (x0, y0) = corner(i % self.width, i / self.width);
+            if let Some(bg) = style.bg {
+                root.draw(&Rectangle::new(
+                    [(x0, y0), (x0 + CELL, y0 + CELL)],
+                    rgb(bg).filled(),
+                ))
+                .map_err(plot_err)?;
+            }
+            if c != '.' && c != ' ' {
+                let fg = style.fg.map(rgb).unwrap_or(BLACK);
+                root.draw(&Rectangle::new(
+                    [(x0 + 2, y0 + 2), (x0 + CELL - 2, y0 + CELL - 2)],
+                    fg.filled(),
+                ))
+                .map_err(plot_err)?;
+            }
+        }
+
+        for overlay in &self.overlays {
+            if let Overlay::Path(positions, color) = overlay {
+                root.draw(&PathElement::new(
+                    positions.iter().copied().map(centre).collect::<Vec<_>>(),
+                    rgb(*color).stroke_width(2),
+                ))
+                .map_err(plot_err)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Side of a cell in pixels, when drawing frames as images
+#[cfg(feature = "plotters")]
+const CELL_PX: i32 = 8;
+
+#[cfg(feature = "plotters")]
+pub(crate) fn plot_err<E: std::error::Error + Send + Sync>(
+    e: plotters::drawing::DrawingAreaErrorKind<E>,
+) -> RenderError {
+    RenderError::Plot(e.to_string())
+}
+
+/// Draws closed polygons to a PNG of the given size, each in its own colour. Points are in
+/// cartesian coordinates (`y` going up), and the axes are fitted to the first polygon.
+#[cfg(feature = "plotters")]
+pub fn polygons(
+    path: &std::path::Path,
+    size: (u32, u32),
+    polygons: &[(Vec<(i64, i64)>, Color)],
+) -> Result<(), RenderError> {
+    use plotters::prelude::*;
+
+    let root = BitMapBackend::new(path, size).into_drawing_area();
+    root.fill(&WHITE).map_err(plot_err)?;
+
+    let (first, _) = polygons
+        .first()
+        .ok_or(RenderError::Plot("no polygons".into()))?;
+    let min_x = first.iter().map(|p| p.0).min().unwrap();
+    let max_x = first.iter().map(|p| p.0).max().unwrap();
+    let min_y = first.iter().map(|p| p.1).min().unwrap();
+    let max_y = first.iter().map(|p| p.1).max().unwrap();
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(10)
+        .y_label_area_size(10)
+        .build_cartesian_2d(min_x..max_x, min_y..max_y)
+        .map_err(plot_err)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()
+        .map_err(plot_err)?;
+
+    let plotting_area = chart.plotting_area();
+    for (points, color) in polygons {
+        plotting_area
+            .draw(&PathElement::new(
+                points
+                    .iter()
+                    .chain(points.first())
+                    .copied()
+                    .collect::<Vec<_>>(),
+                rgb(*color).mix(0.5),
+            ))
+            .map_err(plot_err)?;
+    }
+    root.present().map_err(plot_err)?;
+    Ok(())
+}
+
+/// Arrow pointing from `from` towards `to`
+fn arrow(from: (usize, usize), to: (usize, usize)) -> char {
+    use std::cmp::Ordering::*;
+    match (to.0.cmp(&from.0), to.1.cmp(&from.1)) {
+        (Greater, _) => '>',
+        (Less, _) => '<',
+        (Equal, Greater) => 'v',
+        (Equal, Less) => '^',
+        (Equal, Equal) => '*',
+    }
+}
+
+/// Terminal colours as RGB, using the usual xterm palette
+#[cfg(feature = "plotters")]
+pub(crate) fn rgb(color: Color) -> plotters::style::RGBColor {
+    use plotters::style::RGBColor;
+    match color {
+        Color::Black => RGBColor(0, 0, 0),
+        Color::Red => RGBColor(205, 0, 0),
+        Color::Green => RGBColor(0, 205, 0),
+        Color::Yellow => RGBColor(205, 205, 0),
+        Color::Blue => RGBColor(0, 0, 238),
+        Color::Magenta => RGBColor(205, 0, 205),
+        Color::Cyan => RGBColor(0, 205, 205),
+        Color::White => RGBColor(229, 229, 229),
+        Color::BrightBlack => RGBColor(127, 127, 127),
+        Color::BrightRed => RGBColor(255, 0, 0),
+        Color::BrightGreen => RGBColor(0, 255, 0),
+        Color::BrightYellow => RGBColor(255, 255, 0),
+        Color::BrightBlue => RGBColor(92, 92, 255),
+        Color::BrightMagenta => RGBColor(255, 0, 255),
+        Color::BrightCyan => RGBColor(0, 255, 255),
+        Color::BrightWhite => RGBColor(255, 255, 255),
+        Color::TrueColor { r, g, b } => RGBColor(r, g, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, Frame, Target};
+
+    #[test]
+    fn plain_path() {
+        let mut frame = Frame::new(3, 2, |x, y| if (x + y) % 2 == 0 { '#' } else { '.' });
+        assert_eq!(frame.text(false), "#.#\n.#.\n");
+
+        frame.highlight([(1, 1)], Color::Red);
+        frame.path([(0, 0), (1, 0), (1, 1), (2, 1)], Color::Green);
+        assert_eq!(frame.text(false), ">v#\n.>.\n");
+    }
+
+    #[test]
+    fn parse_target() {
+        assert_eq!(Target::parse("", "p1").unwrap(), Target::Ansi);
+        assert_eq!(Target::parse("plain", "p1").unwrap(), Target::Plain);
+        assert_eq!(
+            Target::parse("png", "p1").unwrap(),
+            Target::Png("inputs/p1".into())
+        );
+        assert_eq!(
+            Target::parse("png:/tmp/out", "p1").unwrap(),
+            Target::Png("/tmp/out".into())
+        );
+        assert!(Target::parse("gif", "p1").is_err());
+    }
+}